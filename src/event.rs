@@ -0,0 +1,110 @@
+use std::fmt;
+use std::ops::Range;
+use std::sync::mpsc::{self, Receiver, Sender};
+
+use crate::queue::RepeatMode;
+
+/// A change of an [OldQueue](crate::queue::OldQueue), sent to its listeners
+/// after the change happened.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum QueueEvent {
+    /// The current item or the current track inside it changed, or the queue
+    /// started or stopped playing.
+    CurrentChanged,
+    /// Items were inserted at the given indices in a part of the queue. For
+    /// [QueuePart::Queue], these are indices in the playback order.
+    ItemsInserted { part: QueuePart, range: Range<usize> },
+    /// Items were removed from the given indices in a part of the queue. For
+    /// [QueuePart::Queue], these are indices in the playback order from
+    /// before the removal.
+    ItemsRemoved { part: QueuePart, range: Range<usize> },
+    /// The item at index `from` in the playback order moved to index `to`.
+    ItemsMoved { from: usize, to: usize },
+    /// The items that didn't play yet were shuffled.
+    Shuffled,
+    /// The queue was unshuffled, see
+    /// [UnshuffleStrategy](crate::queue::UnshuffleStrategy).
+    Unshuffled,
+    /// The repeat mode changed to the given mode.
+    RepeatModeChanged(Option<RepeatMode>),
+    /// All the items and the history were removed.
+    Cleared,
+    /// The queue couldn't go to the next item, because it reached the end.
+    ReachedEnd,
+    /// An edit was undone or redone, so anything may have changed.
+    Restored,
+}
+
+/// A part of an [OldQueue](crate::queue::OldQueue) that contains items.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum QueuePart {
+    /// The items added with
+    /// [OldQueue::queue_next](crate::queue::OldQueue::queue_next), which play
+    /// before the rest of the queue.
+    NextUp,
+    /// The rest of the queue.
+    Queue,
+}
+
+/// Identifies a callback that was added with
+/// [OldQueue::add_listener](crate::queue::OldQueue::add_listener), so it can
+/// be removed again.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ListenerId(usize);
+
+/// A callback that is called for every [QueueEvent].
+type Callback = Box<dyn FnMut(&QueueEvent) + Send>;
+
+/// The listeners that receive the events of a queue. Listeners belong to one
+/// queue, so a clone of the queue starts without any.
+#[derive(Default)]
+pub(crate) struct Listeners {
+    next_id: usize,
+    callbacks: Vec<(ListenerId, Callback)>,
+    senders: Vec<Sender<QueueEvent>>,
+}
+
+impl Listeners {
+    pub fn add<F: FnMut(&QueueEvent) + Send + 'static>(&mut self, callback: F) -> ListenerId {
+        let id = ListenerId(self.next_id);
+        self.next_id += 1;
+        self.callbacks.push((id, Box::new(callback)));
+        id
+    }
+
+    /// Remove the callback with the given id and return whether it existed.
+    pub fn remove(&mut self, id: ListenerId) -> bool {
+        let len = self.callbacks.len();
+        self.callbacks.retain(|(callback_id, _)| *callback_id != id);
+        self.callbacks.len() != len
+    }
+
+    pub fn subscribe(&mut self) -> Receiver<QueueEvent> {
+        let (sender, receiver) = mpsc::channel();
+        self.senders.push(sender);
+        receiver
+    }
+
+    pub fn emit(&mut self, event: QueueEvent) {
+        for (_, callback) in self.callbacks.iter_mut() {
+            callback(&event);
+        }
+        // Forget the channels of which the receiver was dropped
+        self.senders.retain(|sender| sender.send(event.clone()).is_ok());
+    }
+}
+
+impl Clone for Listeners {
+    fn clone(&self) -> Self {
+        Self::default()
+    }
+}
+
+impl fmt::Debug for Listeners {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Listeners")
+            .field("callbacks", &self.callbacks.len())
+            .field("senders", &self.senders.len())
+            .finish()
+    }
+}
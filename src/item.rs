@@ -1,39 +1,116 @@
 use std::fmt::Debug;
 
+use rand::RngCore;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::queue::QueueError;
+use crate::util::shuffled_vec;
+
 /// A collection that can be queued as a QueueItem.
 pub trait QueueableCollection {
     type Item;
 
     /// Get the item at the given index, taking into account the shuffle status.
-    fn get_at_index(&self, index: usize) -> &Self::Item;
+    /// Returns [QueueError::IndexOutOfBounds] if there is no such item.
+    fn get_at_index(&self, index: usize) -> Result<&Self::Item, QueueError>;
 
     /// Get the item at the given index, not taking into account the shuffle status.
-    fn get_at_index_raw(&self, index: usize) -> &Self::Item;
+    /// Returns [QueueError::IndexOutOfBounds] if there is no such item.
+    fn get_at_index_raw(&self, index: usize) -> Result<&Self::Item, QueueError>;
+
+    /// The amount of items in the collection.
+    fn len(&self) -> usize;
+
+    /// Whether the collection has no items.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Whether the collection is shuffled.
+    fn is_shuffled(&self) -> bool;
 
-    /// Shuffle the collection.
-    fn shuffle(&mut self);
+    /// Iterate over the items, taking into account the shuffle status.
+    fn iter(&self) -> Iter<'_, Self> {
+        Iter {
+            collection: self,
+            index: 0,
+            raw: false,
+        }
+    }
+
+    /// Iterate over the items, not taking into account the shuffle status.
+    fn iter_raw(&self) -> Iter<'_, Self> {
+        Iter {
+            collection: self,
+            index: 0,
+            raw: true,
+        }
+    }
+
+    /// Shuffle the collection, using `rng` for the randomness. The queues
+    /// pass their own generator, so the order depends on their seed.
+    fn shuffle(&mut self, rng: &mut dyn RngCore);
 
     /// Unshuffle the collection.
     fn unshuffle(&mut self);
 
-    /// Toggle the shuffle status of the collection.
-    fn toggle_shuffle(&mut self);
+    /// Unshuffle the items after the given index, keeping the items up to and
+    /// including `index` in their place. This is used to unshuffle a
+    /// collection while the item at `index` is playing.
+    fn unshuffle_after(&mut self, index: usize);
+
+    /// Toggle the shuffle status of the collection, using `rng` if it is
+    /// shuffled.
+    fn toggle_shuffle(&mut self, rng: &mut dyn RngCore);
+}
+
+/// An iterator over the items of a [QueueableCollection], either in playback
+/// order or in the original order.
+pub struct Iter<'a, C: QueueableCollection + ?Sized> {
+    collection: &'a C,
+    index: usize,
+    raw: bool,
 }
 
+impl<'a, C: QueueableCollection + ?Sized> Iterator for Iter<'a, C> {
+    type Item = &'a C::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let item = if self.raw {
+            self.collection.get_at_index_raw(self.index)
+        } else {
+            self.collection.get_at_index(self.index)
+        };
+        self.index += 1;
+        item.ok()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.collection.len().saturating_sub(self.index);
+        (remaining, Some(remaining))
+    }
+}
+
+impl<C: QueueableCollection + ?Sized> ExactSizeIterator for Iter<'_, C> {}
+
 /// A simple collection of items that implements the QueueableCollection
 /// interface.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct SimpleCollection<T> {
     /// The items inside the collection for which the SimpleCollection provides
     /// the QueueableCollection functionality.
     items: Vec<T>,
-    shuffled: bool,
+    /// If the collection is shuffled, this contains the playback order.
+    shuffle_order: Option<Vec<usize>>,
 }
 
 impl<T> From<Vec<T>> for SimpleCollection<T> {
     fn from(items: Vec<T>) -> Self {
         Self {
             items,
-            shuffled: false,
+            shuffle_order: None,
         }
     }
 }
@@ -41,29 +118,61 @@ impl<T> From<Vec<T>> for SimpleCollection<T> {
 impl<T> QueueableCollection for SimpleCollection<T> {
     type Item = T;
 
-    fn get_at_index(&self, index: usize) -> &Self::Item {
-        &self.items[index]
+    fn get_at_index(&self, index: usize) -> Result<&Self::Item, QueueError> {
+        let raw_index = match self.shuffle_order {
+            Some(ref shuffle_indices) => shuffle_indices.get(index).copied(),
+            None => Some(index),
+        };
+        raw_index
+            .and_then(|raw_index| self.items.get(raw_index))
+            .ok_or(QueueError::IndexOutOfBounds)
     }
 
-    fn get_at_index_raw(&self, index: usize) -> &Self::Item {
-        &self.items[index]
+    fn get_at_index_raw(&self, index: usize) -> Result<&Self::Item, QueueError> {
+        self.items.get(index).ok_or(QueueError::IndexOutOfBounds)
     }
 
-    fn shuffle(&mut self) {
-        self.shuffled = true;
+    fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    fn is_shuffled(&self) -> bool {
+        self.shuffle_order.is_some()
+    }
+
+    fn shuffle(&mut self, rng: &mut dyn RngCore) {
+        self.shuffle_order = Some(shuffled_vec(self.items.len(), rng));
     }
 
     fn unshuffle(&mut self) {
-        self.shuffled = false;
+        self.shuffle_order = None;
+    }
+
+    /// The collection stays shuffled if the kept items aren't in their
+    /// original order, so the item at `index` keeps playing.
+    fn unshuffle_after(&mut self, index: usize) {
+        if let Some(ref mut shuffle_indices) = self.shuffle_order {
+            if index + 1 < shuffle_indices.len() {
+                shuffle_indices[index+1..].sort();
+            }
+            if shuffle_indices.iter().enumerate().all(|(i, raw)| i == *raw) {
+                self.shuffle_order = None;
+            }
+        }
     }
 
-    fn toggle_shuffle(&mut self) {
-        self.shuffled = !self.shuffled;
+    fn toggle_shuffle(&mut self, rng: &mut dyn RngCore) {
+        if self.is_shuffled() {
+            self.unshuffle();
+        } else {
+            self.shuffle(rng);
+        }
     }
 }
 
 /// A type that can directly be queued.
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum QueueItem<I, C: QueueableCollection> {
     /// A single item that can be queued, like a track or episode.
     Single(I),
@@ -71,3 +180,119 @@ pub enum QueueItem<I, C: QueueableCollection> {
     /// functionality.
     Collection(C),
 }
+
+impl<I, C: QueueableCollection> QueueItem<I, C> {
+    /// The amount of tracks that play for this item. This is always 1 for
+    /// single items.
+    pub fn track_count(&self) -> usize {
+        match self {
+            QueueItem::Single(_) => 1,
+            QueueItem::Collection(collection) => collection.len(),
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::util::seeded_rng;
+
+    /// The id of an item in the tests. A collection has the id of its first
+    /// track.
+    pub fn id(item: &QueueItem<u32, SimpleCollection<u32>>) -> u32 {
+        match item {
+            QueueItem::Single(id) => *id,
+            QueueItem::Collection(collection) => *collection.get_at_index_raw(0).unwrap(),
+        }
+    }
+
+    #[test]
+    fn simple_collection_len() {
+        let collection = SimpleCollection::from(vec![1, 2, 3]);
+        assert_eq!(collection.len(), 3);
+        assert!(!collection.is_empty());
+        assert!(SimpleCollection::<u32>::from(vec![]).is_empty());
+    }
+
+    #[test]
+    fn simple_collection_iter() {
+        let mut collection = SimpleCollection::from(vec![1, 2, 3]);
+        assert_eq!(collection.iter().copied().collect::<Vec<_>>(), vec![1, 2, 3]);
+        assert_eq!(collection.iter_raw().copied().collect::<Vec<_>>(), vec![1, 2, 3]);
+        assert_eq!(collection.iter().len(), 3);
+
+        assert!(!collection.is_shuffled());
+        collection.shuffle_order = Some(vec![2, 0, 1]);
+        assert!(collection.is_shuffled());
+        assert_eq!(collection.iter().copied().collect::<Vec<_>>(), vec![3, 1, 2]);
+        assert_eq!(collection.iter_raw().copied().collect::<Vec<_>>(), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn simple_collection_shuffle() {
+        let mut collection = SimpleCollection::from((0..32).collect::<Vec<u32>>());
+        let mut rng = seeded_rng(3);
+
+        collection.shuffle(&mut rng);
+        assert!(collection.is_shuffled());
+        let mut items: Vec<u32> = collection.iter().copied().collect();
+        items.sort();
+        assert_eq!(items, (0..32).collect::<Vec<u32>>());
+        assert_eq!(*collection.get_at_index_raw(5).unwrap(), 5);
+        let shuffle_indices = collection.shuffle_order.clone().unwrap();
+        assert_eq!(*collection.get_at_index(5).unwrap(), shuffle_indices[5] as u32);
+
+        collection.unshuffle();
+        assert!(!collection.is_shuffled());
+        assert_eq!(*collection.get_at_index(5).unwrap(), 5);
+
+        collection.toggle_shuffle(&mut rng);
+        assert!(collection.is_shuffled());
+        collection.toggle_shuffle(&mut rng);
+        assert!(!collection.is_shuffled());
+
+        // The same seed results in the same order
+        let mut other = SimpleCollection::from((0..32).collect::<Vec<u32>>());
+        other.shuffle(&mut seeded_rng(3));
+        assert_eq!(other.shuffle_order, Some(shuffle_indices));
+    }
+
+    #[test]
+    fn simple_collection_unshuffle_after() {
+        let mut collection = SimpleCollection::from(vec![0, 1, 2, 3, 4, 5]);
+        collection.shuffle_order = Some(vec![4, 1, 5, 0, 3, 2]);
+
+        collection.unshuffle_after(1);
+        assert_eq!(collection.shuffle_order, Some(vec![4, 1, 0, 2, 3, 5]));
+        assert_eq!(*collection.get_at_index(1).unwrap(), 1);
+        assert_eq!(*collection.get_at_index(2).unwrap(), 0);
+
+        collection.shuffle_order = Some(vec![0, 1, 5, 3, 2, 4]);
+        collection.unshuffle_after(1);
+        assert!(!collection.is_shuffled());
+
+        collection.shuffle_order = Some(vec![2, 1, 0]);
+        collection.unshuffle_after(2);
+        assert_eq!(collection.shuffle_order, Some(vec![2, 1, 0]));
+    }
+
+    #[test]
+    fn simple_collection_out_of_bounds() {
+        let mut collection = SimpleCollection::from(vec![1, 2, 3]);
+        assert_eq!(collection.get_at_index(3), Err(QueueError::IndexOutOfBounds));
+        assert_eq!(collection.get_at_index_raw(3), Err(QueueError::IndexOutOfBounds));
+        collection.shuffle(&mut seeded_rng(0));
+        assert_eq!(collection.get_at_index(3), Err(QueueError::IndexOutOfBounds));
+        collection.unshuffle_after(7);
+        assert_eq!(SimpleCollection::<u32>::from(vec![]).iter().next(), None);
+    }
+
+    #[test]
+    fn track_count() {
+        let single: QueueItem<u32, SimpleCollection<u32>> = QueueItem::Single(1);
+        let collection: QueueItem<u32, SimpleCollection<u32>> =
+            QueueItem::Collection(SimpleCollection::from(vec![1, 2, 3]));
+        assert_eq!(single.track_count(), 1);
+        assert_eq!(collection.track_count(), 3);
+    }
+}
@@ -1,3 +1,8 @@
+pub mod event;
 pub mod item;
+pub mod play_queue;
+pub mod playlist;
 pub mod queue;
+pub mod shared;
+pub mod shuffle;
 mod util;
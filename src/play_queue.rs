@@ -0,0 +1,385 @@
+use crate::item::{QueueItem, QueueableCollection};
+use crate::queue::{OldQueue, Queue, QueueError, RepeatMode};
+
+/// The operations that [OldQueue] and [Queue] have in common, so code can be
+/// written once for both of them, and moved from one to the other without
+/// changing.
+///
+/// Both queues behave the same for these operations while they are playing
+/// forward. The differences are:
+/// - [OldQueue] goes through collections track by track, [Queue] plays a
+///   collection as one item.
+/// - [OldQueue::previous] goes back through the history, including the items
+///   added with `queue_next`. [Queue::previous] goes back in the playback
+///   order, see there.
+/// - Queuing an item while the queue is stopped starts playback with
+///   [Queue], but not with [OldQueue]. [PlayQueue::play] starts both.
+/// - [Queue] keeps the history when it's cleared, [OldQueue] doesn't.
+/// - [Queue] also shuffles the items added with `queue_next`, including
+///   with [Queue::shuffle_with]. [OldQueue] always plays them in the order
+///   they were added.
+pub trait PlayQueue<I, C: QueueableCollection> {
+    /// Start playing if the queue is stopped. Returns
+    /// [QueueError::EmptyQueue] if there is nothing to play.
+    fn play(&mut self) -> Result<(), QueueError>;
+
+    /// Go to the next item. Returns [QueueError::ReachedEnd] at the end of
+    /// the queue, unless it repeats.
+    fn next(&mut self) -> Result<(), QueueError>;
+
+    /// Go back to the item that played before the current one. Returns
+    /// [QueueError::ReachedBeginning] if nothing played before it.
+    fn previous(&mut self) -> Result<(), QueueError>;
+
+    /// Add an item to the end of the queue. If the queue is shuffled, the
+    /// item is put at a random place in the unplayed part of the queue.
+    fn queue(&mut self, item: QueueItem<I, C>);
+
+    /// Add an item that plays after the current item and the items that were
+    /// already added with `queue_next`, before the rest of the queue.
+    fn queue_next(&mut self, item: QueueItem<I, C>);
+
+    /// Remove all the items and stop playing. The queue stays shuffled, so
+    /// items that are added later are put at a random place.
+    fn clear(&mut self);
+
+    /// Return whether the queue is shuffled.
+    fn is_shuffled(&self) -> bool;
+
+    /// Shuffle the items that didn't play yet. The current item keeps
+    /// playing.
+    fn shuffle(&mut self);
+
+    /// Put the items that didn't play yet back in the order they were added.
+    /// The current item keeps playing.
+    fn unshuffle(&mut self);
+
+    /// Toggle shuffle.
+    fn toggle_shuffle(&mut self) {
+        if self.is_shuffled() {
+            self.unshuffle();
+        } else {
+            self.shuffle();
+        }
+    }
+
+    /// Get the repeat mode of the queue.
+    fn repeat_status(&self) -> Option<RepeatMode>;
+
+    /// Set the repeat mode of the queue.
+    fn set_repeat_status(&mut self, repeat_status: Option<RepeatMode>);
+
+    /// Get the currently playing item.
+    fn current(&self) -> Result<&QueueItem<I, C>, QueueError>;
+
+    /// Get the current item followed by the items that play after it, in
+    /// playback order.
+    fn upcoming(&self) -> Vec<&QueueItem<I, C>>;
+
+    /// Return whether an item is playing.
+    fn is_playing(&self) -> bool;
+}
+
+impl<I, C: QueueableCollection> PlayQueue<I, C> for OldQueue<I, C> {
+    fn play(&mut self) -> Result<(), QueueError> {
+        OldQueue::play(self)
+    }
+
+    fn next(&mut self) -> Result<(), QueueError> {
+        OldQueue::next(self)
+    }
+
+    fn previous(&mut self) -> Result<(), QueueError> {
+        OldQueue::previous(self)
+    }
+
+    fn queue(&mut self, item: QueueItem<I, C>) {
+        OldQueue::queue(self, item)
+    }
+
+    fn queue_next(&mut self, item: QueueItem<I, C>) {
+        OldQueue::queue_next(self, item)
+    }
+
+    fn clear(&mut self) {
+        OldQueue::clear(self)
+    }
+
+    fn is_shuffled(&self) -> bool {
+        OldQueue::is_shuffled(self)
+    }
+
+    fn shuffle(&mut self) {
+        OldQueue::shuffle(self)
+    }
+
+    fn unshuffle(&mut self) {
+        OldQueue::unshuffle(self)
+    }
+
+    fn toggle_shuffle(&mut self) {
+        OldQueue::toggle_shuffle(self)
+    }
+
+    fn repeat_status(&self) -> Option<RepeatMode> {
+        OldQueue::repeat_status(self)
+    }
+
+    fn set_repeat_status(&mut self, repeat_status: Option<RepeatMode>) {
+        OldQueue::set_repeat_status(self, repeat_status)
+    }
+
+    fn current(&self) -> Result<&QueueItem<I, C>, QueueError> {
+        self.get_current_item()
+    }
+
+    fn upcoming(&self) -> Vec<&QueueItem<I, C>> {
+        OldQueue::upcoming(self)
+    }
+
+    fn is_playing(&self) -> bool {
+        OldQueue::is_playing(self)
+    }
+}
+
+impl<I, C: QueueableCollection> PlayQueue<I, C> for Queue<I, C> {
+    fn play(&mut self) -> Result<(), QueueError> {
+        Queue::play(self)
+    }
+
+    fn next(&mut self) -> Result<(), QueueError> {
+        Queue::next(self)
+    }
+
+    fn previous(&mut self) -> Result<(), QueueError> {
+        Queue::previous(self)
+    }
+
+    fn queue(&mut self, item: QueueItem<I, C>) {
+        Queue::queue(self, item)
+    }
+
+    fn queue_next(&mut self, item: QueueItem<I, C>) {
+        Queue::queue_next(self, item)
+    }
+
+    fn clear(&mut self) {
+        Queue::clear(self)
+    }
+
+    fn is_shuffled(&self) -> bool {
+        Queue::is_shuffled(self)
+    }
+
+    fn shuffle(&mut self) {
+        Queue::shuffle(self)
+    }
+
+    fn unshuffle(&mut self) {
+        Queue::unshuffle(self)
+    }
+
+    fn repeat_status(&self) -> Option<RepeatMode> {
+        Queue::repeat_status(self)
+    }
+
+    fn set_repeat_status(&mut self, repeat_status: Option<RepeatMode>) {
+        Queue::set_repeat_status(self, repeat_status)
+    }
+
+    fn current(&self) -> Result<&QueueItem<I, C>, QueueError> {
+        Queue::current(self)
+    }
+
+    fn upcoming(&self) -> Vec<&QueueItem<I, C>> {
+        Queue::upcoming(self)
+    }
+
+    fn is_playing(&self) -> bool {
+        Queue::is_playing(self)
+    }
+}
+
+/// The same scenarios, run against every [PlayQueue] implementation. The
+/// queues only contain single items, because collections play differently.
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::item::tests::id;
+    use crate::item::SimpleCollection;
+
+    type Item = QueueItem<u32, SimpleCollection<u32>>;
+
+    fn new_queue<Q: PlayQueue<u32, SimpleCollection<u32>> + From<Vec<Item>>>(ids: std::ops::Range<u32>) -> Q {
+        Q::from(ids.map(QueueItem::Single).collect())
+    }
+
+    fn current_id<Q: PlayQueue<u32, SimpleCollection<u32>>>(queue: &Q) -> u32 {
+        id(queue.current().unwrap())
+    }
+
+    fn upcoming_ids<Q: PlayQueue<u32, SimpleCollection<u32>>>(queue: &Q) -> Vec<u32> {
+        queue.upcoming().into_iter().map(id).collect()
+    }
+
+    fn navigation<Q: PlayQueue<u32, SimpleCollection<u32>> + From<Vec<Item>>>() {
+        let mut queue: Q = new_queue(0..3);
+        assert!(queue.is_playing());
+        assert_eq!(current_id(&queue), 0);
+        assert_eq!(queue.previous(), Err(QueueError::ReachedBeginning));
+        queue.next().unwrap();
+        queue.next().unwrap();
+        assert_eq!(current_id(&queue), 2);
+        assert_eq!(queue.next(), Err(QueueError::ReachedEnd));
+        assert_eq!(current_id(&queue), 2);
+        queue.previous().unwrap();
+        assert_eq!(current_id(&queue), 1);
+        queue.previous().unwrap();
+        assert_eq!(current_id(&queue), 0);
+        queue.next().unwrap();
+        assert_eq!(current_id(&queue), 1);
+        assert_eq!(upcoming_ids(&queue), vec![1, 2]);
+    }
+
+    fn queuing<Q: PlayQueue<u32, SimpleCollection<u32>> + From<Vec<Item>>>() {
+        let mut queue: Q = new_queue(0..3);
+        queue.queue(QueueItem::Single(3));
+        queue.queue_next(QueueItem::Single(10));
+        queue.queue_next(QueueItem::Single(11));
+        assert_eq!(upcoming_ids(&queue), vec![0, 10, 11, 1, 2, 3]);
+
+        let mut played = vec![current_id(&queue)];
+        while queue.next().is_ok() {
+            played.push(current_id(&queue));
+        }
+        assert_eq!(played, vec![0, 10, 11, 1, 2, 3]);
+
+        queue.clear();
+        assert!(!queue.is_playing());
+        assert!(queue.upcoming().is_empty());
+        assert_eq!(queue.current().err(), Some(QueueError::NotPlaying));
+    }
+
+    fn shuffling<Q: PlayQueue<u32, SimpleCollection<u32>> + From<Vec<Item>>>() {
+        let mut queue: Q = new_queue(0..20);
+        queue.next().unwrap();
+        assert!(!queue.is_shuffled());
+
+        queue.toggle_shuffle();
+        assert!(queue.is_shuffled());
+        let upcoming = upcoming_ids(&queue);
+        assert_eq!(upcoming[0], 1);
+        let mut sorted = upcoming.clone();
+        sorted.sort();
+        assert_eq!(sorted, (1..20).collect::<Vec<u32>>());
+
+        queue.toggle_shuffle();
+        assert!(!queue.is_shuffled());
+        assert_eq!(upcoming_ids(&queue), (1..20).collect::<Vec<u32>>());
+        queue.previous().unwrap();
+        assert_eq!(current_id(&queue), 0);
+    }
+
+    fn reshuffling<Q: PlayQueue<u32, SimpleCollection<u32>> + From<Vec<Item>>>() {
+        let mut queue: Q = new_queue(0..20);
+        queue.next().unwrap();
+        queue.toggle_shuffle();
+        queue.next().unwrap();
+
+        // The played items stay shuffled, but the queue isn't anymore
+        let current = current_id(&queue);
+        queue.toggle_shuffle();
+        assert!(!queue.is_shuffled());
+        let unplayed = upcoming_ids(&queue)[1..].to_vec();
+        let mut sorted = unplayed.clone();
+        sorted.sort();
+        assert_eq!(unplayed, sorted);
+
+        queue.toggle_shuffle();
+        assert!(queue.is_shuffled());
+        assert_eq!(current_id(&queue), current);
+        let mut reshuffled = upcoming_ids(&queue)[1..].to_vec();
+        reshuffled.sort();
+        assert_eq!(reshuffled, sorted);
+    }
+
+    fn restarting<Q: PlayQueue<u32, SimpleCollection<u32>> + From<Vec<Item>>>() {
+        let mut queue: Q = new_queue(0..3);
+        queue.shuffle();
+        queue.clear();
+        assert!(queue.is_shuffled());
+        assert_eq!(queue.play(), Err(QueueError::EmptyQueue));
+
+        queue.queue(QueueItem::Single(3));
+        queue.queue(QueueItem::Single(4));
+        queue.play().unwrap();
+        queue.play().unwrap();
+        let first = current_id(&queue);
+        queue.next().unwrap();
+        let mut played = vec![first, current_id(&queue)];
+        played.sort();
+        assert_eq!(played, vec![3, 4]);
+        assert_eq!(queue.next(), Err(QueueError::ReachedEnd));
+    }
+
+    fn repeating<Q: PlayQueue<u32, SimpleCollection<u32>> + From<Vec<Item>>>() {
+        let mut queue: Q = new_queue(0..2);
+        assert_eq!(queue.repeat_status(), None);
+        queue.set_repeat_status(Some(RepeatMode::Item));
+        assert_eq!(queue.repeat_status(), Some(RepeatMode::Item));
+        queue.next().unwrap();
+        assert_eq!(current_id(&queue), 0);
+
+        queue.set_repeat_status(Some(RepeatMode::All));
+        queue.next().unwrap();
+        assert_eq!(current_id(&queue), 1);
+        queue.next().unwrap();
+        assert_eq!(current_id(&queue), 0);
+
+        queue.set_repeat_status(None);
+        queue.next().unwrap();
+        assert_eq!(queue.next(), Err(QueueError::ReachedEnd));
+    }
+
+    /// Run every scenario against the given implementation.
+    macro_rules! conformance_tests {
+        ($name:ident, $queue:ty) => {
+            mod $name {
+                use super::*;
+
+                #[test]
+                fn navigation() {
+                    super::navigation::<$queue>();
+                }
+
+                #[test]
+                fn queuing() {
+                    super::queuing::<$queue>();
+                }
+
+                #[test]
+                fn shuffling() {
+                    super::shuffling::<$queue>();
+                }
+
+                #[test]
+                fn reshuffling() {
+                    super::reshuffling::<$queue>();
+                }
+
+                #[test]
+                fn restarting() {
+                    super::restarting::<$queue>();
+                }
+
+                #[test]
+                fn repeating() {
+                    super::repeating::<$queue>();
+                }
+            }
+        };
+    }
+
+    conformance_tests!(old_queue, OldQueue<u32, SimpleCollection<u32>>);
+    conformance_tests!(queue, Queue<u32, SimpleCollection<u32>>);
+}
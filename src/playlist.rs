@@ -0,0 +1,121 @@
+use std::error::Error;
+use std::fmt;
+use std::time::Duration;
+
+pub mod m3u;
+pub mod pls;
+pub mod xspf;
+
+use crate::item::{QueueItem, QueueableCollection};
+
+/// A track in a playlist file.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Entry {
+    /// The path or URL of the track. Relative paths are kept as they are.
+    pub path: String,
+    /// The title of the track, if known.
+    pub title: Option<String>,
+    /// The duration of the track, if known.
+    pub duration: Option<Duration>,
+}
+
+/// An item that can be saved in and loaded from playlist files.
+pub trait PlaylistItem {
+    /// Get the path, title and duration of the item.
+    fn to_entry(&self) -> Entry;
+
+    /// Create an item from a track in a playlist file.
+    fn from_entry(entry: Entry) -> Self;
+}
+
+/// Get the entries of all the tracks of the items, in playback order.
+/// Collections result in an entry for each of their tracks.
+fn track_entries<'a, I, C>(items: impl IntoIterator<Item = &'a QueueItem<I, C>>) -> Vec<Entry>
+where
+    I: PlaylistItem + 'a,
+    C: QueueableCollection<Item = I> + 'a,
+{
+    let mut entries = Vec::new();
+    for item in items {
+        match item {
+            QueueItem::Single(single) => entries.push(single.to_entry()),
+            QueueItem::Collection(collection) => {
+                entries.extend(collection.iter().map(PlaylistItem::to_entry))
+            }
+        }
+    }
+    entries
+}
+
+/// Get the duration in whole seconds, rounded to the nearest second, for the
+/// formats that only store seconds.
+fn rounded_secs(duration: Duration) -> u64 {
+    (duration + Duration::from_millis(500)).as_secs()
+}
+
+/// Errors that can occur while reading a playlist file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PlaylistError {
+    /// The document isn't valid XML.
+    InvalidXml(roxmltree::Error),
+    /// The document doesn't have the expected root element.
+    UnexpectedRoot(String),
+}
+
+impl fmt::Display for PlaylistError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PlaylistError::InvalidXml(error) => write!(f, "invalid XML: {}", error),
+            PlaylistError::UnexpectedRoot(name) => write!(f, "unexpected root element <{}>", name),
+        }
+    }
+}
+
+impl Error for PlaylistError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            PlaylistError::InvalidXml(error) => Some(error),
+            PlaylistError::UnexpectedRoot(_) => None,
+        }
+    }
+}
+
+impl From<roxmltree::Error> for PlaylistError {
+    fn from(error: roxmltree::Error) -> Self {
+        PlaylistError::InvalidXml(error)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::item::SimpleCollection;
+
+    impl PlaylistItem for Entry {
+        fn to_entry(&self) -> Entry {
+            self.clone()
+        }
+
+        fn from_entry(entry: Entry) -> Self {
+            entry
+        }
+    }
+
+    pub fn entry(path: &str, title: Option<&str>, seconds: Option<u64>) -> Entry {
+        Entry {
+            path: path.to_string(),
+            title: title.map(str::to_string),
+            duration: seconds.map(Duration::from_secs),
+        }
+    }
+
+    pub fn entries(items: &[QueueItem<Entry, SimpleCollection<Entry>>]) -> Vec<Entry> {
+        items
+            .iter()
+            .map(|item| match item {
+                QueueItem::Single(entry) => entry.clone(),
+                QueueItem::Collection(_) => panic!("expected a single item"),
+            })
+            .collect()
+    }
+}
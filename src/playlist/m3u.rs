@@ -0,0 +1,211 @@
+//! Extended M3U8 playlists.
+//!
+//! ```text
+//! #EXTM3U
+//! #EXTINF:215,Artist - Title
+//! music/track.mp3
+//! ```
+
+use std::fmt::Write;
+use std::time::Duration;
+
+use crate::item::{QueueItem, QueueableCollection};
+use crate::playlist::{rounded_secs, track_entries, Entry, PlaylistItem};
+
+const HEADER: &str = "#EXTM3U";
+const EXTINF: &str = "#EXTINF:";
+
+/// Write the items as an extended M3U8 document, in the given order.
+/// Collections are written as all of their tracks, in playback order.
+///
+/// Pass [OldQueue::upcoming](crate::queue::OldQueue::upcoming) or
+/// [Queue::upcoming](crate::queue::Queue::upcoming) to export a queue in its
+/// current playback order.
+pub fn write<'a, I, C>(items: impl IntoIterator<Item = &'a QueueItem<I, C>>) -> String
+where
+    I: PlaylistItem + 'a,
+    C: QueueableCollection<Item = I> + 'a,
+{
+    let mut document = String::from(HEADER);
+    document.push('\n');
+    for entry in track_entries(items) {
+        write_entry(&mut document, entry);
+    }
+    document
+}
+
+fn write_entry(document: &mut String, entry: Entry) {
+    if entry.title.is_some() || entry.duration.is_some() {
+        // -1 is the conventional duration for unknown lengths
+        let seconds = entry.duration.map_or(-1, |duration| rounded_secs(duration) as i64);
+        let title = entry.title.as_deref().unwrap_or_default();
+        // Writing to a String can't fail
+        let _ = writeln!(document, "{}{},{}", EXTINF, seconds, title);
+    }
+    document.push_str(&entry.path);
+    document.push('\n');
+}
+
+/// Read the tracks of an M3U or M3U8 document as single items. Comments and
+/// `#EXTINF` lines that can't be parsed are ignored, and paths are kept as
+/// they are written in the document.
+pub fn read<I: PlaylistItem, C: QueueableCollection>(document: &str) -> Vec<QueueItem<I, C>> {
+    let mut items = Vec::new();
+    let mut info: Option<(Option<Duration>, Option<String>)> = None;
+    for line in document.trim_start_matches('\u{feff}').lines() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        if let Some(extinf) = line.strip_prefix(EXTINF) {
+            info = Some(parse_extinf(extinf));
+        } else if !line.starts_with('#') {
+            let (duration, title) = info.take().unwrap_or_default();
+            items.push(QueueItem::Single(I::from_entry(Entry {
+                path: line.to_string(),
+                title,
+                duration,
+            })));
+        }
+    }
+    items
+}
+
+/// Parse the part of an `#EXTINF` line after the colon, which is the
+/// duration in seconds, optionally followed by attributes, and the title after
+/// the first comma.
+fn parse_extinf(extinf: &str) -> (Option<Duration>, Option<String>) {
+    let (properties, title) = extinf.split_once(',').unwrap_or((extinf, ""));
+    let duration = properties
+        .split_whitespace()
+        .next()
+        .and_then(|seconds| seconds.parse::<f64>().ok())
+        // Negative, infinite and too long durations are unknown
+        .and_then(|seconds| Duration::try_from_secs_f64(seconds).ok());
+    let title = title.trim();
+    let title = if title.is_empty() { None } else { Some(title.to_string()) };
+    (duration, title)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::item::SimpleCollection;
+    use crate::playlist::tests::{entries, entry};
+    use crate::queue::{OldQueue, Queue};
+
+    #[test]
+    fn m3u_write() {
+        let items: Vec<QueueItem<Entry, SimpleCollection<Entry>>> = vec![
+            QueueItem::Single(entry("a.mp3", Some("Artist - A"), Some(215))),
+            QueueItem::Collection(SimpleCollection::from(vec![
+                entry("album/b.flac", None, Some(60)),
+                entry("album/c.flac", Some("C"), None),
+            ])),
+            QueueItem::Single(entry("https://example.com/d.ogg", None, None)),
+        ];
+        assert_eq!(
+            write(&items),
+            "#EXTM3U\n\
+             #EXTINF:215,Artist - A\n\
+             a.mp3\n\
+             #EXTINF:60,\n\
+             album/b.flac\n\
+             #EXTINF:-1,C\n\
+             album/c.flac\n\
+             https://example.com/d.ogg\n"
+        );
+    }
+
+    #[test]
+    fn m3u_write_rounds_durations() {
+        let tracks = [Duration::from_millis(239_900), Duration::from_millis(60_499), Duration::from_millis(500)];
+        let items: Vec<QueueItem<Entry, SimpleCollection<Entry>>> = tracks
+            .into_iter()
+            .map(|duration| {
+                QueueItem::Single(Entry {
+                    path: "a.mp3".to_string(),
+                    title: None,
+                    duration: Some(duration),
+                })
+            })
+            .collect();
+        assert_eq!(
+            write(&items),
+            "#EXTM3U\n#EXTINF:240,\na.mp3\n#EXTINF:60,\na.mp3\n#EXTINF:1,\na.mp3\n"
+        );
+    }
+
+    #[test]
+    fn m3u_read() {
+        let document = "\u{feff}#EXTM3U\r\n\
+                        # a comment\r\n\
+                        #EXTINF:12.5 tvg-id=\"x\",Title, with comma\r\n\
+                        \r\n\
+                        ../music/a.mp3\r\n\
+                        #EXTINF:-1,\r\n\
+                        b.mp3\r\n\
+                        #EXTINF:oops\r\n\
+                        /music/c.mp3\r\n\
+                        d.mp3";
+        let items: Vec<QueueItem<Entry, SimpleCollection<Entry>>> = read(document);
+        assert_eq!(
+            entries(&items),
+            vec![
+                Entry {
+                    path: "../music/a.mp3".to_string(),
+                    title: Some("Title, with comma".to_string()),
+                    duration: Some(Duration::from_millis(12500)),
+                },
+                entry("b.mp3", None, None),
+                entry("/music/c.mp3", None, None),
+                entry("d.mp3", None, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn m3u_read_invalid_durations() {
+        let document = "#EXTM3U\n#EXTINF:1e30,Title\na.mp3\n#EXTINF:inf,\nb.mp3\n#EXTINF:NaN,\nc.mp3\n";
+        let items: Vec<QueueItem<Entry, SimpleCollection<Entry>>> = read(document);
+        assert_eq!(
+            entries(&items),
+            vec![
+                entry("a.mp3", Some("Title"), None),
+                entry("b.mp3", None, None),
+                entry("c.mp3", None, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn m3u_round_trip() {
+        let tracks = vec![
+            entry("../music/a.mp3", Some("Artist - A"), Some(215)),
+            entry("relative/b.mp3", Some("B"), None),
+            entry("c.mp3", None, Some(3)),
+            entry("/absolute/d.mp3", None, None),
+        ];
+        let items: Vec<QueueItem<Entry, SimpleCollection<Entry>>> =
+            tracks.iter().cloned().map(QueueItem::Single).collect();
+        let read_items: Vec<QueueItem<Entry, SimpleCollection<Entry>>> = read(&write(&items));
+        assert_eq!(entries(&read_items), tracks);
+    }
+
+    #[test]
+    fn m3u_queue_playback_order() {
+        let items: Vec<QueueItem<Entry, SimpleCollection<Entry>>> = (0..4)
+            .map(|i| QueueItem::Single(entry(&format!("{}.mp3", i), None, None)))
+            .collect();
+
+        let mut old_queue = OldQueue::from(items.clone());
+        old_queue.next().unwrap();
+        old_queue.queue_next(QueueItem::Single(entry("next.mp3", None, None)));
+        assert_eq!(write(old_queue.upcoming()), "#EXTM3U\n1.mp3\nnext.mp3\n2.mp3\n3.mp3\n");
+
+        let mut queue = Queue::from(items);
+        queue.next().unwrap();
+        queue.queue_next(QueueItem::Single(entry("next.mp3", None, None)));
+        assert_eq!(write(queue.upcoming()), "#EXTM3U\n1.mp3\nnext.mp3\n2.mp3\n3.mp3\n");
+    }
+}
@@ -0,0 +1,156 @@
+//! PLS playlists.
+//!
+//! ```text
+//! [playlist]
+//! File1=music/track.mp3
+//! Title1=Artist - Title
+//! Length1=215
+//! NumberOfEntries=1
+//! Version=2
+//! ```
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::time::Duration;
+
+use crate::item::{QueueItem, QueueableCollection};
+use crate::playlist::{rounded_secs, track_entries, Entry, PlaylistItem};
+
+const HEADER: &str = "[playlist]";
+
+/// Write the items as a PLS document, in the given order. Collections are
+/// written as all of their tracks, in playback order.
+pub fn write<'a, I, C>(items: impl IntoIterator<Item = &'a QueueItem<I, C>>) -> String
+where
+    I: PlaylistItem + 'a,
+    C: QueueableCollection<Item = I> + 'a,
+{
+    let entries = track_entries(items);
+    let mut document = String::from(HEADER);
+    document.push('\n');
+    // Writing to a String can't fail
+    for (index, entry) in entries.iter().enumerate() {
+        let number = index + 1;
+        let _ = writeln!(document, "File{}={}", number, entry.path);
+        if let Some(ref title) = entry.title {
+            let _ = writeln!(document, "Title{}={}", number, title);
+        }
+        if let Some(duration) = entry.duration {
+            let _ = writeln!(document, "Length{}={}", number, rounded_secs(duration));
+        }
+    }
+    let _ = writeln!(document, "NumberOfEntries={}", entries.len());
+    document.push_str("Version=2\n");
+    document
+}
+
+/// Read the tracks of a PLS document as single items, ordered by their
+/// number. Keys are case insensitive, lines that can't be parsed are ignored
+/// and tracks without a `File` key are skipped. Paths are kept as they are
+/// written in the document.
+pub fn read<I: PlaylistItem, C: QueueableCollection>(document: &str) -> Vec<QueueItem<I, C>> {
+    let mut entries: BTreeMap<usize, (Option<String>, Entry)> = BTreeMap::new();
+    for line in document.trim_start_matches('\u{feff}').lines() {
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let key = key.trim().to_ascii_lowercase();
+        let value = value.trim();
+        let Some((name, number)) = split_key(&key) else {
+            continue;
+        };
+        let (path, entry) = entries.entry(number).or_default();
+        match name {
+            "file" => *path = Some(value.to_string()),
+            "title" if !value.is_empty() => entry.title = Some(value.to_string()),
+            // Negative lengths mean the length is unknown
+            "length" => entry.duration = value.parse().ok().map(Duration::from_secs),
+            _ => {}
+        }
+    }
+    entries
+        .into_values()
+        .filter_map(|(path, entry)| {
+            path.map(|path| QueueItem::Single(I::from_entry(Entry { path, ..entry })))
+        })
+        .collect()
+}
+
+/// Split a key like `file12` into its name and number.
+fn split_key(key: &str) -> Option<(&str, usize)> {
+    let digits = key.find(|c: char| c.is_ascii_digit())?;
+    let (name, number) = key.split_at(digits);
+    Some((name, number.parse().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::item::SimpleCollection;
+    use crate::playlist::tests::{entries, entry};
+
+    #[test]
+    fn pls_write() {
+        let items: Vec<QueueItem<Entry, SimpleCollection<Entry>>> = vec![
+            QueueItem::Single(entry("a.mp3", Some("Artist - A"), Some(215))),
+            QueueItem::Collection(SimpleCollection::from(vec![
+                Entry {
+                    path: "album/b.flac".to_string(),
+                    title: None,
+                    duration: Some(Duration::from_millis(59_600)),
+                },
+                entry("album/c.flac", Some("C"), None),
+            ])),
+        ];
+        assert_eq!(
+            write(&items),
+            "[playlist]\n\
+             File1=a.mp3\n\
+             Title1=Artist - A\n\
+             Length1=215\n\
+             File2=album/b.flac\n\
+             Length2=60\n\
+             File3=album/c.flac\n\
+             Title3=C\n\
+             NumberOfEntries=3\n\
+             Version=2\n"
+        );
+    }
+
+    #[test]
+    fn pls_read() {
+        let document = "[playlist]\r\n\
+                        NumberOfEntries=4\r\n\
+                        File2=http://example.com/stream\r\n\
+                        Title2=Stream\r\n\
+                        Length2=-1\r\n\
+                        file1 = ../music/a.mp3\r\n\
+                        LENGTH1=12\r\n\
+                        Title3=No file\r\n\
+                        File10=j.mp3\r\n\
+                        not a key\r\n\
+                        Version=2";
+        let items: Vec<QueueItem<Entry, SimpleCollection<Entry>>> = read(document);
+        assert_eq!(
+            entries(&items),
+            vec![
+                entry("../music/a.mp3", None, Some(12)),
+                entry("http://example.com/stream", Some("Stream"), None),
+                entry("j.mp3", None, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn pls_round_trip() {
+        let tracks = vec![
+            entry("../music/a.mp3", Some("Artist - A"), Some(215)),
+            entry("relative/b.mp3", Some("B"), None),
+            entry("/absolute/c.mp3", None, Some(3)),
+        ];
+        let items: Vec<QueueItem<Entry, SimpleCollection<Entry>>> =
+            tracks.iter().cloned().map(QueueItem::Single).collect();
+        let read_items: Vec<QueueItem<Entry, SimpleCollection<Entry>>> = read(&write(&items));
+        assert_eq!(entries(&read_items), tracks);
+    }
+}
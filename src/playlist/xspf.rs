@@ -0,0 +1,252 @@
+//! XSPF playlists.
+//!
+//! ```text
+//! <?xml version="1.0" encoding="UTF-8"?>
+//! <playlist version="1" xmlns="http://xspf.org/ns/0/">
+//!   <trackList>
+//!     <track>
+//!       <location>music/track.mp3</location>
+//!       <title>Artist - Title</title>
+//!       <duration>215000</duration>
+//!     </track>
+//!   </trackList>
+//! </playlist>
+//! ```
+
+use std::fmt::Write;
+use std::time::Duration;
+
+use roxmltree::{Document, Node};
+
+use crate::item::{QueueItem, QueueableCollection};
+use crate::playlist::{track_entries, Entry, PlaylistError, PlaylistItem};
+
+/// Write the items as an XSPF document, in the given order. Collections are
+/// written as all of their tracks, in playback order.
+pub fn write<'a, I, C>(items: impl IntoIterator<Item = &'a QueueItem<I, C>>) -> String
+where
+    I: PlaylistItem + 'a,
+    C: QueueableCollection<Item = I> + 'a,
+{
+    let mut document = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <playlist version=\"1\" xmlns=\"http://xspf.org/ns/0/\">\n  \
+         <trackList>\n",
+    );
+    // Writing to a String can't fail
+    for entry in track_entries(items) {
+        document.push_str("    <track>\n");
+        let _ = writeln!(document, "      <location>{}</location>", escape(&entry.path));
+        if let Some(ref title) = entry.title {
+            let _ = writeln!(document, "      <title>{}</title>", escape(title));
+        }
+        if let Some(duration) = entry.duration {
+            let _ = writeln!(document, "      <duration>{}</duration>", duration.as_millis());
+        }
+        document.push_str("    </track>\n");
+    }
+    document.push_str("  </trackList>\n</playlist>\n");
+    document
+}
+
+/// Read the tracks of an XSPF document as single items. Tracks without a
+/// location are skipped, and locations are kept as they are written in the
+/// document.
+pub fn read<I: PlaylistItem, C: QueueableCollection>(
+    document: &str,
+) -> Result<Vec<QueueItem<I, C>>, PlaylistError> {
+    read_with(document, |_| None)
+}
+
+/// Read the tracks of an XSPF document like [read], but call `resolve` for
+/// every track first. If it returns a collection, the track is a reference to
+/// another playlist and becomes a [QueueItem::Collection]. Otherwise it
+/// becomes a [QueueItem::Single]. This can be used to load nested playlists,
+/// for example by reading the tracks of locations that end in `.xspf`.
+pub fn read_with<I, C, F>(document: &str, mut resolve: F) -> Result<Vec<QueueItem<I, C>>, PlaylistError>
+where
+    I: PlaylistItem,
+    C: QueueableCollection,
+    F: FnMut(&Entry) -> Option<C>,
+{
+    let document = Document::parse(document.trim_start_matches('\u{feff}'))?;
+    let playlist = document.root_element();
+    if playlist.tag_name().name() != "playlist" {
+        return Err(PlaylistError::UnexpectedRoot(playlist.tag_name().name().to_string()));
+    }
+    let tracks = child(playlist, "trackList")
+        .into_iter()
+        .flat_map(|track_list| track_list.children())
+        .filter(|node| node.tag_name().name() == "track");
+    let mut items = Vec::new();
+    for track in tracks {
+        let Some(path) = child_text(track, "location") else {
+            continue;
+        };
+        let entry = Entry {
+            path,
+            title: child_text(track, "title"),
+            duration: child_text(track, "duration")
+                .and_then(|millis| millis.parse().ok())
+                .map(Duration::from_millis),
+        };
+        items.push(match resolve(&entry) {
+            Some(collection) => QueueItem::Collection(collection),
+            None => QueueItem::Single(I::from_entry(entry)),
+        });
+    }
+    Ok(items)
+}
+
+/// Get the first child element of `node` with the given name, in any
+/// namespace.
+fn child<'a, 'input>(node: Node<'a, 'input>, name: &str) -> Option<Node<'a, 'input>> {
+    node.children().find(|child| child.tag_name().name() == name)
+}
+
+/// Get the trimmed text of the first child element of `node` with the given
+/// name, if it isn't empty.
+fn child_text(node: Node, name: &str) -> Option<String> {
+    let text = child(node, name)?.text()?.trim();
+    if text.is_empty() {
+        None
+    } else {
+        Some(text.to_string())
+    }
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::item::SimpleCollection;
+    use crate::playlist::tests::{entries, entry};
+
+    #[test]
+    fn xspf_write() {
+        let items: Vec<QueueItem<Entry, SimpleCollection<Entry>>> = vec![
+            QueueItem::Single(entry("a.mp3", Some("Rock & <Roll>"), Some(215))),
+            QueueItem::Collection(SimpleCollection::from(vec![entry("album/b.flac", None, None)])),
+        ];
+        assert_eq!(
+            write(&items),
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <playlist version=\"1\" xmlns=\"http://xspf.org/ns/0/\">\n  \
+             <trackList>\n    \
+             <track>\n      \
+             <location>a.mp3</location>\n      \
+             <title>Rock &amp; &lt;Roll&gt;</title>\n      \
+             <duration>215000</duration>\n    \
+             </track>\n    \
+             <track>\n      \
+             <location>album/b.flac</location>\n    \
+             </track>\n  \
+             </trackList>\n\
+             </playlist>\n"
+        );
+    }
+
+    #[test]
+    fn xspf_read() {
+        let document = r#"<?xml version="1.0" encoding="UTF-8"?>
+            <playlist version="1" xmlns="http://xspf.org/ns/0/">
+              <title>Ignored</title>
+              <trackList>
+                <track>
+                  <location>file:///music/a.mp3</location>
+                  <title> A </title>
+                  <duration>1500</duration>
+                </track>
+                <track>
+                  <identifier>no location</identifier>
+                </track>
+                <track>
+                  <location>../b.mp3</location>
+                  <duration>unknown</duration>
+                </track>
+              </trackList>
+            </playlist>"#;
+        let items: Vec<QueueItem<Entry, SimpleCollection<Entry>>> = read(document).unwrap();
+        assert_eq!(
+            entries(&items),
+            vec![
+                Entry {
+                    path: "file:///music/a.mp3".to_string(),
+                    title: Some("A".to_string()),
+                    duration: Some(Duration::from_millis(1500)),
+                },
+                entry("../b.mp3", None, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn xspf_read_errors() {
+        assert!(matches!(
+            read::<Entry, SimpleCollection<Entry>>("<playlist>"),
+            Err(PlaylistError::InvalidXml(_))
+        ));
+        assert_eq!(
+            read::<Entry, SimpleCollection<Entry>>("<html></html>").unwrap_err(),
+            PlaylistError::UnexpectedRoot("html".to_string())
+        );
+        assert!(read::<Entry, SimpleCollection<Entry>>("<playlist/>").unwrap().is_empty());
+    }
+
+    #[test]
+    fn xspf_read_nested_playlists() {
+        let nested = write::<Entry, SimpleCollection<Entry>>(&[
+            QueueItem::Single(entry("album/1.mp3", None, None)),
+            QueueItem::Single(entry("album/2.mp3", None, None)),
+        ]);
+        let document = write::<Entry, SimpleCollection<Entry>>(&[
+            QueueItem::Single(entry("a.mp3", None, None)),
+            QueueItem::Single(entry("album.xspf", Some("Album"), None)),
+        ]);
+        let items: Vec<QueueItem<Entry, SimpleCollection<Entry>>> = read_with(&document, |entry| {
+            entry.path.ends_with(".xspf").then(|| {
+                let tracks = read::<Entry, SimpleCollection<Entry>>(&nested).unwrap();
+                SimpleCollection::from(entries(&tracks))
+            })
+        })
+        .unwrap();
+
+        assert_eq!(items.len(), 2);
+        assert!(matches!(items[0], QueueItem::Single(ref track) if track.path == "a.mp3"));
+        match items[1] {
+            QueueItem::Collection(ref collection) => assert_eq!(
+                collection.iter().cloned().collect::<Vec<_>>(),
+                vec![entry("album/1.mp3", None, None), entry("album/2.mp3", None, None)]
+            ),
+            QueueItem::Single(_) => panic!("expected a collection"),
+        }
+    }
+
+    #[test]
+    fn xspf_round_trip() {
+        let tracks = vec![
+            entry("../music/a.mp3", Some("Artist - A & B"), Some(215)),
+            entry("relative/b.mp3", Some("B"), None),
+            entry("/absolute/c.mp3", None, Some(3)),
+        ];
+        let items: Vec<QueueItem<Entry, SimpleCollection<Entry>>> =
+            tracks.iter().cloned().map(QueueItem::Single).collect();
+        let read_items: Vec<QueueItem<Entry, SimpleCollection<Entry>>> =
+            read(&write(&items)).unwrap();
+        assert_eq!(entries(&read_items), tracks);
+    }
+}
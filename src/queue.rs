@@ -1,10 +1,24 @@
-use std::rc::Rc;
-
-use rand::seq::SliceRandom;
-
+use std::borrow::Cow;
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fmt;
+use std::ops::{Index, Range};
+use std::sync::mpsc::Receiver;
+use std::sync::Arc;
+
+use rand::Rng;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::event::{ListenerId, Listeners, QueueEvent, QueuePart};
 use crate::item::QueueItem;
 use crate::item::QueueableCollection;
-use crate::util::shuffled_vec;
+use crate::shuffle::{shuffle_raw_indices, ShuffleAlgorithm, Uniform};
+use crate::util::{insert_indices, is_permutation, remove_indices, reorder, seeded_rng, shuffled_vec, QueueRng};
+
+/// The amount of edits that can be undone by default, see
+/// [OldQueue::with_undo_depth].
+const DEFAULT_UNDO_DEPTH: usize = 20;
 
 /// An advanced, configurable music queue.
 ///
@@ -19,26 +33,68 @@ use crate::util::shuffled_vec;
 ///     - All
 ///     - Off
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct OldQueue<I, C: QueueableCollection> {
-    /// Indices showing previously played songs. The history before the
-    /// current_item can never change.
-    history: Vec<usize>,
+    /// Indices showing previously played songs, as the index of the item and
+    /// the index of the track inside that item. Playing only adds entries at
+    /// the end, the played songs themselves never change. Editing the items
+    /// does change the entries: they are remapped when items move in `items`,
+    /// and the entries of removed items are removed, see `remap_history`.
+    history: Vec<(ItemIndex, usize)>,
     /// If the user went backwards, they are now in the history, and this index
     /// shows where in the history. Can move forwards and backwards!
     history_index: Option<usize>,
-    pub repeat_status: Option<RepeatMode>,
+    /// The repeat mode of the queue. Only changed with
+    /// [OldQueue::set_repeat_status], so the listeners are informed.
+    repeat_status: Option<RepeatMode>,
+    /// Whether the queue should be reshuffled when it starts over because of
+    /// [RepeatMode::All]. Only has an effect if the queue is shuffled.
+    reshuffle_on_repeat: bool,
     unshuffle_strat: UnshuffleStrategy,
     /// If the queue is shuffled, this contains the playback order.
     shuffle_order: Option<Vec<usize>>,
-    /// The index of the next up item (the short term part of the queue).
+    /// Whether the queue is shuffled. Depending on the [UnshuffleStrategy],
+    /// `shuffle_order` is kept after [OldQueue::unshuffle], so the items that
+    /// already played keep their place.
+    shuffled: bool,
+    /// The seed of `rng`. Shuffling the same items with the same seed always
+    /// results in the same `shuffle_order`.
+    seed: u64,
+    /// The random number generator used for shuffling.
+    rng: QueueRng,
+    /// The index of the currently playing next up item (the short term part of
+    /// the queue), if the current item comes from `next_up_items`.
     current_next_up_item: Option<usize>,
+    /// The index of the next up item that plays next. All the items before it
+    /// already played.
+    next_up_index: usize,
     /// The short term queue, used by the `queue_next` method. Items in this queue
     /// are always played before items in the main queue.
     next_up_items: Vec<QueueItem<I, C>>,
     /// The index of the currently playing item, if any. Can only move forwards!
+    /// While a next up item is playing, this is the index of the item that
+    /// plays after the next up items.
     current_item: Option<usize>,
+    /// The index of the currently playing track inside the current item,
+    /// taking into account the shuffle status of the collection. Always 0 for
+    /// single items.
+    track_index: usize,
     /// Items is a collection of items that this queue can play.
     items: Vec<QueueItem<I, C>>,
+    /// The edits that can be undone with [OldQueue::undo], the most recent
+    /// one at the back. Not saved with the queue.
+    #[cfg_attr(feature = "serde", serde(skip, default = "VecDeque::new"))]
+    undo_stack: VecDeque<UndoEntry<I, C>>,
+    /// The edits that were undone and can be redone with [OldQueue::redo],
+    /// the most recent one at the back. Not saved with the queue.
+    #[cfg_attr(feature = "serde", serde(skip, default = "Vec::new"))]
+    redo_stack: Vec<UndoEntry<I, C>>,
+    /// The maximum amount of edits in `undo_stack`.
+    undo_depth: usize,
+    /// The listeners that receive the events of the queue. Not saved with
+    /// the queue.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    listeners: Listeners,
 }
 
 /// An advanced, configurable music queue.
@@ -53,107 +109,344 @@ pub struct OldQueue<I, C: QueueableCollection> {
 ///     - Container
 ///     - All
 ///     - Off
+///
+/// The items are kept in an [Arc], so the queue can be sent to and shared
+/// with other threads if the items can. See
+/// [SharedQueue](crate::shared::SharedQueue) to use it from several threads
+/// at once.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Queue<I, C: QueueableCollection> {
-    /// Index in the `queue`, pointing to the currently playing item. 
+    /// Index in the `queue`, pointing to the currently playing item.
     ///
     /// - If the `queue` is empty, `index` should be None.
-    /// `queue` == \[\] -> None
+    ///   `queue` == \[\] -> None
     /// - If the `queue` isn't empty, `index` points to the currently playing
-    /// item.
-    /// `queue` == \["item 1", "item 2", "item 3"\] && next up is "item 2" -> 1
+    ///   item.
+    ///   `queue` == \["item 1", "item 2", "item 3"\] && playing "item 2" -> 1
+    /// - If an item from the `short_term_queue` is playing, `index` points to
+    ///   the item of the `queue` that will play after the short term queue is
+    ///   done. This equals the length of the `queue` if it is done.
+    ///   `queue` == \["item 1", "item 2", "item 3"\] && next up is "item 2" -> 1
     index: Option<usize>,
     /// The normal part of the `Queue`, which functions as any normal queue.
     /// Songs in the `queue` are in the same order as when they were added.
-    queue: Vec<Rc<QueueItem<I, C>>>,
+    queue: Vec<Arc<QueueItem<I, C>>>,
     /// If `queue` is shuffled, this contains the playback order.
     order: Option<Vec<usize>>,
-    /// Index into `short_term_queue`, pointing to the currently playing item.
+    /// Index into `short_term_queue`, pointing to the next item that should
+    /// be played from it.
     ///
-    /// - If `short_term_queue` is empty, this should be None. 
-    /// `short_term_queue` == \[\] -> None
+    /// - If `short_term_queue` is empty, this should be None.
+    ///   `short_term_queue` == \[\] -> None
     /// - If `short_term_queue` is done, this should equal the length of the
-    /// short term queue. This makes sure that when a new item is added to the
-    /// short term queue, it is automatically played before the normal queue.
-    /// `short_term_queue` == \["item 1", "item 2", "item 3"\] && done -> 3
-    /// - Otherwise, this points to the next item that should be played. 
-    /// `short_term_queue` == \["item 1", "item 2", "item 3"\] && next up is "item 2" -> 1 
+    ///   short term queue. This makes sure that when a new item is added to the
+    ///   short term queue, it is automatically played before the normal queue.
+    ///   `short_term_queue` == \["item 1", "item 2", "item 3"\] && done -> 3
+    /// - Otherwise, this points to the next item that should be played.
+    ///   `short_term_queue` == \["item 1", "item 2", "item 3"\] && next up is "item 2" -> 1
     short_term_index: Option<usize>,
     /// The short term part of the queue. Items added to this queue will always
     /// play before items from the `queue`. Items in the `short_term_queue` are
     /// in the same order as when they were added.
-    short_term_queue: Vec<Rc<QueueItem<I, C>>>,
+    short_term_queue: Vec<Arc<QueueItem<I, C>>>,
     /// If `short_term_queue` is shuffled, this contains the playback order.
     short_term_order: Option<Vec<usize>>,
+    /// Whether the queue is shuffled. After [Queue::unshuffle], the items
+    /// that already played keep their shuffled place, so `order` and
+    /// `short_term_order` can be set while the queue isn't shuffled.
+    shuffled: bool,
+    /// Whether the currently playing item comes from the `short_term_queue`.
+    /// If so, it is the item right before `short_term_index`.
+    playing_short_term: bool,
     /// The history of all the items that were played with this `Queue`.
-    history: Vec<Rc<QueueItem<I, C>>>,
+    history: Vec<Arc<QueueItem<I, C>>>,
     /// The repeat mode of the `Queue`.
     repeat_status: Option<RepeatMode>,
+    /// The seed of `rng`. Shuffling the same items with the same seed always
+    /// results in the same `order`.
+    seed: u64,
+    /// The random number generator used for shuffling.
+    rng: QueueRng,
 }
 
 impl<I, C: QueueableCollection> From<Vec<QueueItem<I, C>>> for OldQueue<I, C> {
     fn from(items: Vec<QueueItem<I, C>>) -> Self {
+        let seed = rand::random();
         OldQueue {
             history: Vec::new(),
             history_index: None,
             repeat_status: None,
+            reshuffle_on_repeat: false,
             unshuffle_strat: UnshuffleStrategy::PlayUnplayed,
             shuffle_order: None,
+            shuffled: false,
+            seed,
+            rng: seeded_rng(seed),
             current_next_up_item: None,
+            next_up_index: 0,
             next_up_items: Vec::new(),
             current_item: if items.is_empty() { None } else { Some(0) },
+            track_index: 0,
             items,
+            undo_stack: VecDeque::new(),
+            redo_stack: Vec::new(),
+            undo_depth: DEFAULT_UNDO_DEPTH,
+            listeners: Listeners::default(),
         }
     }
 }
 
 impl <I, C: QueueableCollection> Default for OldQueue<I, C> {
     fn default() -> Self {
+        let seed = rand::random();
         Self {
             history: Vec::new(),
             history_index: None,
             repeat_status: None,
+            reshuffle_on_repeat: false,
             shuffle_order: None,
+            shuffled: false,
+            seed,
+            rng: seeded_rng(seed),
             unshuffle_strat: UnshuffleStrategy::PlayUnplayed,
             current_next_up_item: None,
+            next_up_index: 0,
             next_up_items: Vec::new(),
             current_item: None,
+            track_index: 0,
             items: Vec::new(),
+            undo_stack: VecDeque::new(),
+            redo_stack: Vec::new(),
+            undo_depth: DEFAULT_UNDO_DEPTH,
+            listeners: Listeners::default(),
         }
     }
 }
 
 impl<I, C: QueueableCollection> OldQueue<I, C> {
+    /// Use the given repeat mode for the queue.
+    pub fn with_repeat_status(mut self, repeat_status: Option<RepeatMode>) -> Self {
+        self.repeat_status = repeat_status;
+        self
+    }
+
+    /// Use the given strategy when unshuffling the queue.
+    pub fn with_unshuffle_strategy(mut self, unshuffle_strat: UnshuffleStrategy) -> Self {
+        self.unshuffle_strat = unshuffle_strat;
+        self
+    }
+
+    /// Use the given seed for shuffling the queue.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.set_seed(seed);
+        self
+    }
+
+    /// Get the seed that is used for shuffling the queue.
+    #[inline]
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Restart shuffling from the given seed. Shuffling the same items with
+    /// the same seed always results in the same playback order.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.seed = seed;
+        self.rng = seeded_rng(seed);
+    }
+
+    /// Get the strategy that is used when unshuffling the queue.
+    #[inline]
+    pub fn unshuffle_strategy(&self) -> UnshuffleStrategy {
+        self.unshuffle_strat
+    }
+
+    /// Set the strategy that is used when unshuffling the queue.
+    /// See [UnshuffleStrategy] for all the options.
+    #[inline]
+    pub fn set_unshuffle_strategy(&mut self, unshuffle_strat: UnshuffleStrategy) {
+        self.unshuffle_strat = unshuffle_strat;
+    }
+
+    /// Keep at most `undo_depth` edits that can be undone with
+    /// [OldQueue::undo]. A depth of 0 disables undo.
+    pub fn with_undo_depth(mut self, undo_depth: usize) -> Self {
+        self.set_undo_depth(undo_depth);
+        self
+    }
+
+    /// Get the maximum amount of edits that can be undone.
+    #[inline]
+    pub fn undo_depth(&self) -> usize {
+        self.undo_depth
+    }
+
+    /// Set the maximum amount of edits that can be undone. If there are more
+    /// edits than that, the oldest ones are forgotten.
+    pub fn set_undo_depth(&mut self, undo_depth: usize) {
+        self.undo_depth = undo_depth;
+        while self.undo_stack.len() > undo_depth {
+            self.undo_stack.pop_front();
+        }
+        let excess = self.redo_stack.len().saturating_sub(undo_depth);
+        self.redo_stack.drain(..excess);
+    }
+
+    /// Get the repeat mode of the queue.
+    #[inline]
+    pub fn repeat_status(&self) -> Option<RepeatMode> {
+        self.repeat_status
+    }
+
+    /// Set the repeat mode of the queue, and send a
+    /// [QueueEvent::RepeatModeChanged] to the listeners if it changed.
+    pub fn set_repeat_status(&mut self, repeat_status: Option<RepeatMode>) {
+        if self.repeat_status != repeat_status {
+            self.repeat_status = repeat_status;
+            self.emit(QueueEvent::RepeatModeChanged(repeat_status));
+        }
+    }
+
+    /// Get whether the queue is reshuffled when it starts over because of
+    /// [RepeatMode::All].
+    #[inline]
+    pub fn reshuffle_on_repeat(&self) -> bool {
+        self.reshuffle_on_repeat
+    }
+
+    /// Set whether the queue is reshuffled when it starts over because of
+    /// [RepeatMode::All]. Only has an effect if the queue is shuffled.
+    pub fn set_reshuffle_on_repeat(&mut self, reshuffle_on_repeat: bool) {
+        self.reshuffle_on_repeat = reshuffle_on_repeat;
+    }
+
+    /// Call `callback` with every [QueueEvent] from now on. Listeners aren't
+    /// cloned or saved with the queue.
+    pub fn add_listener<F: FnMut(&QueueEvent) + Send + 'static>(&mut self, callback: F) -> ListenerId {
+        self.listeners.add(callback)
+    }
+
+    /// Stop calling the callback that was added with
+    /// [OldQueue::add_listener]. Returns whether it was still there.
+    pub fn remove_listener(&mut self, id: ListenerId) -> bool {
+        self.listeners.remove(id)
+    }
+
+    /// Receive every [QueueEvent] from now on through a channel. The channel
+    /// is forgotten when the receiver is dropped.
+    pub fn subscribe(&mut self) -> Receiver<QueueEvent> {
+        self.listeners.subscribe()
+    }
+
+    #[inline]
+    fn emit(&mut self, event: QueueEvent) {
+        self.listeners.emit(event);
+    }
+
     /// Change the current song to the next one in the queue and return whether
-    /// the current song was changed.
+    /// the current song was changed. Collections are played track by track,
+    /// and items added with [OldQueue::queue_next] play before the rest of
+    /// the queue.
+    ///
+    /// When going forward through the history, the repeat mode is ignored.
+    /// Otherwise [RepeatMode::Item] replays the current track,
+    /// [RepeatMode::Container] restarts the current collection when its last
+    /// track ends, and [RepeatMode::All] starts over at the beginning of the
+    /// queue when the end is reached.
     #[allow(clippy::should_implement_trait)]
     pub fn next(&mut self) -> Result<(), QueueError> {
-        if let Some(ref mut index) = self.current_item {
+        let result = self.step_forward();
+        match result {
+            Ok(()) => self.emit(QueueEvent::CurrentChanged),
+            Err(QueueError::ReachedEnd) => self.emit(QueueEvent::ReachedEnd),
+            Err(_) => {}
+        }
+        result
+    }
+
+    /// Go to the next track, see [OldQueue::next].
+    fn step_forward(&mut self) -> Result<(), QueueError> {
+        if let Some(index) = self.current_item {
             // Playing
-            if let Some(ref mut history_index) = self.history_index {
+            if let Some(history_index) = self.history_index {
                 // Going forward through history
-                if *history_index + 1 == *index {
+                if history_index + 1 == self.history.len() {
                     // Caught back up to the present
                     self.history_index = None;
-                    Ok(())
                 } else {
                     // Still inside history
-                    *history_index += 1;
-                    Ok(())
+                    self.history_index = Some(history_index + 1);
                 }
+                Ok(())
             } else {
                 // Not in history, playing normally
-                if *index < self.items.len() - 1 {
-                    // Not at end of queue
-                    if let Some(ref shuffle_indices) = self.shuffle_order {
-                        self.history.push(shuffle_indices[*index]);
-                    } else {
-                        self.history.push(*index);
-                    }
-                    *index += 1;
-                    Ok(())
+                let (current, track_index) = self.current_position()?;
+                let track_count = self.get_item(current).track_count();
+                // While a next up item is playing, `index` didn't play yet.
+                let next_index = if self.current_next_up_item.is_some() {
+                    index
                 } else {
-                    // At end of queue
-                    Err(QueueError::ReachedEnd)
+                    index + 1
+                };
+                match self.repeat_status {
+                    Some(RepeatMode::Item) => {
+                        self.history.push((current, track_index));
+                        Ok(())
+                    }
+                    _ if track_index + 1 < track_count => {
+                        // Not at end of collection
+                        self.history.push((current, track_index));
+                        self.track_index += 1;
+                        Ok(())
+                    }
+                    Some(RepeatMode::Container) => {
+                        // At end of collection, or a single item
+                        self.history.push((current, track_index));
+                        self.track_index = 0;
+                        Ok(())
+                    }
+                    _ if self.next_up_index < self.next_up_items.len() => {
+                        // Next up items play before the rest of the queue
+                        self.history.push((current, track_index));
+                        self.current_item = Some(next_index);
+                        self.current_next_up_item = Some(self.next_up_index);
+                        self.next_up_index += 1;
+                        self.track_index = 0;
+                        Ok(())
+                    }
+                    _ if next_index < self.playback_len() => {
+                        // Not at end of queue
+                        self.history.push((current, track_index));
+                        self.current_item = Some(next_index);
+                        self.current_next_up_item = None;
+                        self.track_index = 0;
+                        Ok(())
+                    }
+                    Some(RepeatMode::All) if self.playback_len() > 0 => {
+                        // At end of queue, start over
+                        self.history.push((current, track_index));
+                        self.current_item = Some(0);
+                        self.current_next_up_item = None;
+                        self.track_index = 0;
+                        if !self.shuffled {
+                            // Unshuffled while playing, start over in the
+                            // original order
+                            self.shuffle_order = None;
+                        } else if self.reshuffle_on_repeat
+                            || !Self::plays_every_item_once(&self.shuffle_order, self.items.len())
+                        {
+                            // The unshuffle strategy can skip or repeat
+                            // items, don't do that again in the new pass
+                            self.shuffle_order = Some(shuffled_vec(self.items.len(), &mut self.rng));
+                            self.emit(QueueEvent::Shuffled);
+                        }
+                        Ok(())
+                    }
+                    _ => {
+                        // At end of queue
+                        Err(QueueError::ReachedEnd)
+                    }
                 }
             }
         } else {
@@ -164,50 +457,173 @@ impl<I, C: QueueableCollection> OldQueue<I, C> {
 
     /// Change the current song to the previous one in the queue and return
     /// whether the current song was changed.
+    ///
+    /// At the beginning of the queue, [RepeatMode::Item] and
+    /// [RepeatMode::Container] replay the current track. If nothing played
+    /// yet, [RepeatMode::All] goes to the last track of the queue.
     pub fn previous(&mut self) -> Result<(), QueueError> {
-        if let Some(index) = self.current_item {
-            if let Some(ref mut history_index) = self.history_index {
-                // User already listening to history.
-                if *history_index > 0 {
-                    *history_index -= 1;
-                    Ok(())
-                } else {
-                    Err(QueueError::ReachedBeginning)
-                }
-            } else {
-                // User went back for the first time.
-                if index > 0 {
-                    self.history_index = Some(index - 1);
-                    Ok(())
-                } else {
-                    Err(QueueError::ReachedBeginning)
-                }
+        let result = self.step_back();
+        if result.is_ok() {
+            self.emit(QueueEvent::CurrentChanged);
+        }
+        result
+    }
+
+    /// Go to the previous track, see [OldQueue::previous].
+    fn step_back(&mut self) -> Result<(), QueueError> {
+        if self.current_item.is_none() {
+            return Err(QueueError::NotPlaying);
+        }
+        match self.history_index {
+            // User already listening to history.
+            Some(ref mut history_index) if *history_index > 0 => {
+                *history_index -= 1;
+                return Ok(());
             }
-        } else {
-            Err(QueueError::NotPlaying)
+            // User went back for the first time.
+            None if !self.history.is_empty() => {
+                self.history_index = Some(self.history.len() - 1);
+                return Ok(());
+            }
+            _ => {}
+        }
+        // At the beginning of the queue
+        match self.repeat_status {
+            Some(RepeatMode::Item) | Some(RepeatMode::Container) => Ok(()),
+            Some(RepeatMode::All) if self.history_index.is_none() && self.playback_len() > 0 => {
+                // Nothing played yet, wrap around to the end of the queue
+                let index = self.playback_len() - 1;
+                self.current_item = Some(index);
+                self.current_next_up_item = None;
+                self.track_index = self.items[self.raw_index(index)].track_count().saturating_sub(1);
+                Ok(())
+            }
+            _ => Err(QueueError::ReachedBeginning),
         }
     }
 
-    // TODO: Properly implement this
-    pub fn play(&mut self) {
+    /// Start playing the queue if it's stopped. Items added with
+    /// [OldQueue::queue_next] play first. Returns [QueueError::EmptyQueue] if
+    /// there is nothing to play.
+    pub fn play(&mut self) -> Result<(), QueueError> {
+        if self.current_item.is_some() {
+            // Already playing
+            return Ok(());
+        }
+        if self.playback_len() == 0 && self.next_up_index >= self.next_up_items.len() {
+            return Err(QueueError::EmptyQueue);
+        }
         self.current_item = Some(0);
-        self.current_next_up_item = Some(0);
+        self.track_index = 0;
+        if self.next_up_index < self.next_up_items.len() {
+            self.current_next_up_item = Some(self.next_up_index);
+            self.next_up_index += 1;
+        }
+        self.emit(QueueEvent::CurrentChanged);
+        Ok(())
     }
 
-    /// Gets the currently playing item.
+    /// Start playing the item at the given index in the playback order right
+    /// away and return it. The current item is added to the history, like
+    /// with [OldQueue::next], and the items between the current item and the
+    /// new one are handled according to `policy`. Jumping to an item that
+    /// already played plays it again.
+    pub fn jump_to(&mut self, index: usize, policy: JumpPolicy) -> Result<&QueueItem<I, C>, QueueError> {
+        if index >= self.playback_len() {
+            return Err(QueueError::IndexOutOfBounds);
+        }
+        let state = self.undo_state();
+        let edit = self.jump(index, policy);
+        self.push_undo(state, edit);
+        self.get_current_item()
+    }
+
+    /// Like [OldQueue::jump_to], but with the index of the item in the order
+    /// the items were added, regardless of the shuffle order.
+    pub fn jump_to_raw(&mut self, raw_index: usize, policy: JumpPolicy) -> Result<&QueueItem<I, C>, QueueError> {
+        if raw_index >= self.items.len() {
+            return Err(QueueError::IndexOutOfBounds);
+        }
+        let state = self.undo_state();
+        let boundary = self.boundary();
+        let positions: Vec<usize> = (0..self.playback_len())
+            .filter(|i| self.raw_index(*i) == raw_index)
+            .collect();
+        // Prefer the place where the item didn't play yet
+        let index = match positions.iter().find(|i| **i >= boundary).or(positions.last()) {
+            Some(index) => *index,
+            None => {
+                // Skipped by the unshuffle strategy, see UnshuffleStrategy::KeepIndex
+                if let Some(ref mut shuffle_indices) = self.shuffle_order {
+                    shuffle_indices.insert(boundary, raw_index);
+                }
+                self.emit(QueueEvent::ItemsInserted {
+                    part: QueuePart::Queue,
+                    range: boundary..boundary + 1,
+                });
+                boundary
+            }
+        };
+        let edit = self.jump(index, policy);
+        self.push_undo(state, edit);
+        self.get_current_item()
+    }
+
+    /// Start playing the item at the given index in the playback order, see
+    /// [OldQueue::jump_to]. Returns the edit that undoes the changes to the
+    /// items.
+    fn jump(&mut self, index: usize, policy: JumpPolicy) -> ItemsEdit<I, C> {
+        // Stop going through the history, the present is now in the past
+        self.history_index = None;
+        if let Ok(position) = self.current_position() {
+            self.history.push(position);
+        }
+        let boundary = self.boundary();
+        let (current, edit) = if index >= boundary && policy == JumpPolicy::AddToHistory {
+            for i in boundary..index {
+                let raw_index = self.raw_index(i);
+                for track_index in 0..self.items[raw_index].track_count() {
+                    self.history.push((ItemIndex::Queue(raw_index), track_index));
+                }
+            }
+            (index, ItemsEdit::None)
+        } else {
+            // Move the item to the first place that didn't play yet
+            let target = if index < boundary { boundary - 1 } else { boundary };
+            let mut order = self.playback_order();
+            let raw_index = order.remove(index);
+            order.insert(target, raw_index);
+            if index != target {
+                self.emit(QueueEvent::ItemsMoved { from: index, to: target });
+            }
+            (target, self.set_playback_order(order))
+        };
+        self.current_item = Some(current);
+        self.current_next_up_item = None;
+        self.track_index = 0;
+        self.emit(QueueEvent::CurrentChanged);
+        edit
+    }
+
+    /// Gets the currently playing item. For collections, this is the whole
+    /// collection, see [OldQueue::current_track] for the track inside it.
     pub fn get_current_item(&self) -> Result<&QueueItem<I, C>, QueueError> {
+        let (index, _) = self.current_position()?;
+        Ok(self.get_item(index))
+    }
+
+    /// The index of the current item and the index of the current track
+    /// inside that item.
+    fn current_position(&self) -> Result<(ItemIndex, usize), QueueError> {
         if let Some(index) = self.current_item {
             // Playing
             if let Some(history_index) = self.history_index {
-                Ok(&self.items[self.history[history_index]])
+                Ok(self.history[history_index])
+            } else if let Some(next_up_index) = self.current_next_up_item {
+                // Playing a next up item
+                Ok((ItemIndex::NextUp(next_up_index), self.track_index))
             } else {
-                if let Some(ref shuffle_indices) = self.shuffle_order {
-                    // Shuffled
-                    Ok(&self.items[shuffle_indices[index]])
-                } else {
-                    // Not shuffled
-                    Ok(&self.items[index])
-                }
+                Ok((ItemIndex::Queue(self.raw_index(index)), self.track_index))
             }
         } else {
             // Stopped
@@ -215,72 +631,563 @@ impl<I, C: QueueableCollection> OldQueue<I, C> {
         }
     }
 
-    pub fn get_items(&self) -> Vec<&QueueItem<I, C>> {
-        let mut items: Vec<&QueueItem<I, C>> = Vec::with_capacity(self.items.len());
+    #[inline]
+    fn get_item(&self, index: ItemIndex) -> &QueueItem<I, C> {
+        match index {
+            ItemIndex::Queue(index) => &self.items[index],
+            ItemIndex::NextUp(index) => &self.next_up_items[index],
+        }
+    }
+
+    #[inline]
+    fn get_item_mut(&mut self, index: ItemIndex) -> &mut QueueItem<I, C> {
+        match index {
+            ItemIndex::Queue(index) => &mut self.items[index],
+            ItemIndex::NextUp(index) => &mut self.next_up_items[index],
+        }
+    }
+
+    /// Translate an index in the playback order to an index in `items`.
+    #[inline]
+    fn raw_index(&self, index: usize) -> usize {
+        match self.shuffle_order {
+            Some(ref shuffle_indices) => shuffle_indices[index],
+            None => index,
+        }
+    }
+
+    /// Whether the playback order `order` contains every one of the `len`
+    /// items exactly once. After [OldQueue::unshuffle], some strategies leave
+    /// out items or repeat them.
+    fn plays_every_item_once(order: &Option<Vec<usize>>, len: usize) -> bool {
+        match order {
+            Some(shuffle_indices) => is_permutation(shuffle_indices, len),
+            None => true,
+        }
+    }
+
+    /// The first index in the playback order of an item that didn't play yet.
+    fn boundary(&self) -> usize {
+        match self.current_item {
+            Some(index) if self.current_next_up_item.is_none() => index + 1,
+            Some(index) => index,
+            None => 0,
+        }
+    }
+
+    /// Get all the items in the queue, split into the items that already
+    /// played, the current item, the next up items and the rest of the queue.
+    /// See [QueueView] for the details.
+    pub fn get_items(&self) -> QueueView<'_, I, C> {
+        let mut view = QueueView {
+            history: self.history.iter().map(|(index, _)| self.get_item(*index)).collect(),
+            history_index: self.history_index,
+            current: None,
+            up_next: self.next_up_items[self.next_up_index..].iter().collect(),
+            remaining: Vec::with_capacity(self.playback_len()),
+        };
         if let Some(index) = self.current_item {
             // Playing
-            for i in &self.history {
-                items.push(&self.items[*i]);
-            }
-            if let Some(ref shuffle_indices) = self.shuffle_order {
-                // Shuffled
-                for i in index..shuffle_indices.len() {
-                    items.push(&self.items[shuffle_indices[i]]);
-                }
+            if let Some(next_up_index) = self.current_next_up_item {
+                // Playing a next up item
+                view.current = Some(&self.next_up_items[next_up_index]);
             } else {
-                // Not shuffled
-                for i in index..self.items.len() {
-                    items.push(&self.items[i]);
-                }
+                view.current = Some(&self.items[self.raw_index(index)]);
             }
-        } else {
-            // Not playing
-            if let Some(ref shuffle_indices) = self.shuffle_order {
-                // Shuffled
-                for index in 0..self.items.len() {
-                    items[index] = &self.items[shuffle_indices[index]];
-                }
-            } else {
-                // Not shuffled
-                for item in &self.items {
-                    items.push(item);
+        }
+        for i in self.boundary()..self.playback_len() {
+            view.remaining.push(&self.items[self.raw_index(i)]);
+        }
+        view
+    }
+
+    /// Get the current item followed by the items that play after it, in
+    /// playback order. Items that already played aren't included, unless the
+    /// user went back in the history, then the history after the current
+    /// item is included.
+    pub fn upcoming(&self) -> Vec<&QueueItem<I, C>> {
+        let mut indices: Vec<ItemIndex> = Vec::new();
+        if let Some(history_index) = self.history_index {
+            // A collection is in the history once for every track of it
+            for (index, _) in self.history[history_index..].iter() {
+                if indices.last() != Some(index) {
+                    indices.push(*index);
                 }
             }
         }
+        let view = self.get_items();
+        let mut items: Vec<&QueueItem<I, C>> = indices.into_iter().map(|index| self.get_item(index)).collect();
+        let catching_up = self.history_index.and_then(|_| self.history.last()).map(|(index, _)| *index);
+        if let Some(current) = view.current {
+            // The current collection may already be partly in the history
+            let current_index = self.current_item.map(|index| match self.current_next_up_item {
+                Some(next_up_index) => ItemIndex::NextUp(next_up_index),
+                None => ItemIndex::Queue(self.raw_index(index)),
+            });
+            if catching_up != current_index {
+                items.push(current);
+            }
+        }
+        items.extend(view.up_next);
+        items.extend(view.remaining);
         items
     }
 
+    /// Get the next `n` tracks that play with [OldQueue::next], without
+    /// changing the queue. This takes into account the history, the next up
+    /// items, the shuffle order, the track inside the current collection and
+    /// the repeat mode, so the tracks can be prepared before they play. With
+    /// [RepeatMode::All], the tracks after the end of the queue are included,
+    /// even if the queue is reshuffled then.
+    pub fn peek_next(&self, n: usize) -> Vec<UpcomingTrack<'_, I, C>> {
+        let mut tracks = Vec::with_capacity(n);
+        let Some(index) = self.current_item else {
+            return tracks;
+        };
+        let track = |(index, track_index): (ItemIndex, usize)| UpcomingTrack {
+            item: self.get_item(index),
+            track_index,
+        };
+        // The position that plays after catching back up with the history
+        let present = match self.current_next_up_item {
+            Some(next_up_index) => (ItemIndex::NextUp(next_up_index), self.track_index),
+            None => (ItemIndex::Queue(self.raw_index(index)), self.track_index),
+        };
+        if let Some(history_index) = self.history_index {
+            // Going forward through the history ignores the repeat mode
+            tracks.extend(self.history[history_index + 1..].iter().copied().take(n).map(track));
+            if tracks.len() < n {
+                tracks.push(track(present));
+            }
+        }
+
+        let mut current = present;
+        let mut index = index;
+        let mut playing_next_up = self.current_next_up_item.is_some();
+        let mut next_up_index = self.next_up_index;
+        let mut shuffle_order = Cow::Borrowed(&self.shuffle_order);
+        let mut rng = None;
+        while tracks.len() < n {
+            let (item, track_index) = current;
+            let next_index = if playing_next_up { index } else { index + 1 };
+            let playback_len = match *shuffle_order {
+                Some(ref shuffle_indices) => shuffle_indices.len(),
+                None => self.items.len(),
+            };
+            let raw_index = |order: &Option<Vec<usize>>, i: usize| match order {
+                Some(shuffle_indices) => shuffle_indices[i],
+                None => i,
+            };
+            current = match self.repeat_status {
+                Some(RepeatMode::Item) => current,
+                _ if track_index + 1 < self.get_item(item).track_count() => (item, track_index + 1),
+                Some(RepeatMode::Container) => (item, 0),
+                _ if next_up_index < self.next_up_items.len() => {
+                    index = next_index;
+                    playing_next_up = true;
+                    next_up_index += 1;
+                    (ItemIndex::NextUp(next_up_index - 1), 0)
+                }
+                _ if next_index < playback_len => {
+                    index = next_index;
+                    playing_next_up = false;
+                    (ItemIndex::Queue(raw_index(&shuffle_order, index)), 0)
+                }
+                Some(RepeatMode::All) if playback_len > 0 => {
+                    index = 0;
+                    playing_next_up = false;
+                    if !self.shuffled {
+                        *shuffle_order.to_mut() = None;
+                    } else if self.reshuffle_on_repeat
+                        || !Self::plays_every_item_once(&shuffle_order, self.items.len())
+                    {
+                        // Shuffle the same way the queue will
+                        let rng = rng.get_or_insert_with(|| self.rng.clone());
+                        *shuffle_order.to_mut() = Some(shuffled_vec(self.items.len(), rng));
+                    }
+                    (ItemIndex::Queue(raw_index(&shuffle_order, 0)), 0)
+                }
+                _ => break,
+            };
+            tracks.push(track(current));
+        }
+        tracks
+    }
+
     #[inline]
     pub fn len(&self) -> usize {
         self.items.len()
     }
 
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// The amount of items in the playback order. Depending on the
+    /// [UnshuffleStrategy], this can differ from the amount of items in the
+    /// queue.
+    #[inline]
+    fn playback_len(&self) -> usize {
+        match self.shuffle_order {
+            Some(ref shuffle_indices) => shuffle_indices.len(),
+            None => self.items.len(),
+        }
+    }
+
+    /// Add an item to the end of the queue. If the queue is shuffled, the
+    /// item is put at a random place in the unplayed part of the queue.
     pub fn queue(&mut self, item: QueueItem<I, C>) {
+        let state = self.undo_state();
+        let boundary = self.boundary();
         self.items.push(item);
-        if let Some(index) = self.current_item {
-            // Playing
-            if let Some(ref mut shuffle_indices) = self.shuffle_order {
-                // Everyday I'm shuffling
-                shuffle_indices.push(shuffle_indices.len());
-                self.shuffle();
+        let raw_index = self.items.len() - 1;
+        let index = match self.shuffle_order {
+            Some(ref mut shuffle_indices) if self.shuffled => {
+                // Put it at a random unplayed place, the order of the other
+                // items stays the same
+                let index = self.rng.gen_range(boundary..=shuffle_indices.len());
+                shuffle_indices.insert(index, raw_index);
+                index
             }
-        }
+            Some(ref mut shuffle_indices) => {
+                shuffle_indices.push(raw_index);
+                shuffle_indices.len() - 1
+            }
+            None => self.items.len() - 1,
+        };
+        self.emit(QueueEvent::ItemsInserted {
+            part: QueuePart::Queue,
+            range: index..index + 1,
+        });
+        let edit = ItemsEdit::Remove {
+            items: vec![self.items.len() - 1],
+            next_up_items: Vec::new(),
+        };
+        self.push_undo(state, edit);
     }
 
+    /// Add an item to the short term part of the queue. It plays after the
+    /// current item and the items that were already added with `queue_next`,
+    /// before the rest of the queue.
     pub fn queue_next(&mut self, item: QueueItem<I, C>) {
+        let state = self.undo_state();
         self.next_up_items.push(item);
+        let index = self.next_up_items.len() - 1;
+        self.emit(QueueEvent::ItemsInserted {
+            part: QueuePart::NextUp,
+            range: index..index + 1,
+        });
+        let edit = ItemsEdit::Remove {
+            items: Vec::new(),
+            next_up_items: vec![self.next_up_items.len() - 1],
+        };
+        self.push_undo(state, edit);
     }
 
-    /// Clear the queue.
+    /// Clear the queue, including the next up items and the history. This can
+    /// be undone with [OldQueue::undo].
     pub fn clear(&mut self) {
-        self.items.clear();
+        let state = self.undo_state();
+        let edit = ItemsEdit::Insert {
+            items: std::mem::take(&mut self.items).into_iter().enumerate().collect(),
+            next_up_items: std::mem::take(&mut self.next_up_items).into_iter().enumerate().collect(),
+        };
+        self.history.clear();
+        self.history_index = None;
         self.current_item = None;
+        self.current_next_up_item = None;
+        self.next_up_index = 0;
+        self.track_index = 0;
+        // Stay shuffled
+        self.shuffle_order = self.shuffled.then(Vec::new);
+        self.emit(QueueEvent::Cleared);
+        self.push_undo(state, edit);
+    }
+
+    /// Remove the item at the given index in the playback order. The item is
+    /// kept so the removal can be undone with [OldQueue::undo], so it isn't
+    /// returned.
+    ///
+    /// If the current item is removed, the next up items start playing, like
+    /// with [OldQueue::next]. If there are none, the item after it starts
+    /// playing, and if there is no item after it, the queue stops. The item
+    /// is also removed from the history.
+    pub fn remove(&mut self, index: usize) -> Result<(), QueueError> {
+        if index >= self.playback_len() {
+            return Err(QueueError::IndexOutOfBounds);
+        }
+        self.remove_range(index..index + 1)
+    }
+
+    /// Remove the items in the given range of the playback order. See
+    /// [OldQueue::remove].
+    pub fn remove_range(&mut self, range: Range<usize>) -> Result<(), QueueError> {
+        if range.start > range.end || range.end > self.playback_len() {
+            return Err(QueueError::IndexOutOfBounds);
+        }
+        let state = self.undo_state();
+        let mut raw_indices: Vec<usize> = range.map(|i| self.raw_index(i)).collect();
+        raw_indices.sort_unstable();
+        raw_indices.dedup();
+        // Items can have more than one place in the playback order, see
+        // UnshuffleStrategy::KeepIndex
+        let positions: Vec<usize> = (0..self.playback_len())
+            .filter(|i| raw_indices.binary_search(&self.raw_index(*i)).is_ok())
+            .collect();
+        let removes_current = match self.history_index {
+            // Going through the history, the current item is in it
+            Some(history_index) => matches!(
+                self.history[history_index].0,
+                ItemIndex::Queue(raw_index) if raw_indices.binary_search(&raw_index).is_ok()
+            ),
+            None => {
+                self.current_next_up_item.is_none()
+                    && self.current_item.is_some_and(|current| positions.contains(&current))
+            }
+        };
+        // Removing from the back doesn't change the smaller indices
+        let mut removed: Vec<(usize, QueueItem<I, C>)> = raw_indices
+            .into_iter()
+            .rev()
+            .map(|raw_index| (raw_index, self.remove_raw(raw_index)))
+            .collect();
+        removed.reverse();
+        let mut ranges: Vec<Range<usize>> = Vec::new();
+        for position in positions {
+            match ranges.last_mut() {
+                Some(range) if range.end == position => range.end += 1,
+                _ => ranges.push(position..position + 1),
+            }
+        }
+        // Report the removed places from the back, so the indices of the
+        // events stay valid
+        for range in ranges.into_iter().rev() {
+            self.emit(QueueEvent::ItemsRemoved {
+                part: QueuePart::Queue,
+                range,
+            });
+        }
+        if self.playback_len() == 0 && !self.items.is_empty() {
+            // The unshuffle strategy left the other items out of the playback
+            // order, see UnshuffleStrategy::KeepIndex. Start over with them,
+            // so they can still play.
+            self.shuffle_order = self
+                .shuffled
+                .then(|| shuffled_vec(self.items.len(), &mut self.rng));
+            self.emit(QueueEvent::ItemsInserted {
+                part: QueuePart::Queue,
+                range: 0..self.items.len(),
+            });
+        }
+        if removes_current {
+            self.emit(QueueEvent::CurrentChanged);
+        }
+        let edit = ItemsEdit::Insert {
+            items: removed,
+            next_up_items: Vec::new(),
+        };
+        self.push_undo(state, edit);
+        Ok(())
+    }
+
+    /// Insert an item at the given index in the playback order. The items
+    /// before the current item count as played, so inserting an item at or
+    /// before the current item means it won't play.
+    pub fn insert_at(&mut self, index: usize, item: QueueItem<I, C>) -> Result<(), QueueError> {
+        if index > self.playback_len() {
+            return Err(QueueError::IndexOutOfBounds);
+        }
+        let state = self.undo_state();
+        let raw_index = if let Some(ref mut shuffle_indices) = self.shuffle_order {
+            // Shuffled
+            self.items.push(item);
+            shuffle_indices.insert(index, self.items.len() - 1);
+            self.items.len() - 1
+        } else {
+            // Not shuffled
+            self.items.insert(index, item);
+            self.remap_history(|raw_index| Some(if raw_index >= index { raw_index + 1 } else { raw_index }));
+            index
+        };
+        if let Some(ref mut current) = self.current_item {
+            if index < *current || (index == *current && self.current_next_up_item.is_none()) {
+                *current += 1;
+            }
+        }
+        self.emit(QueueEvent::ItemsInserted {
+            part: QueuePart::Queue,
+            range: index..index + 1,
+        });
+        let edit = ItemsEdit::Remove {
+            items: vec![raw_index],
+            next_up_items: Vec::new(),
+        };
+        self.push_undo(state, edit);
+        Ok(())
+    }
+
+    /// Move the item at index `from` in the playback order to index `to`. An
+    /// item that moves before the current item counts as played, and one that
+    /// moves after it plays again.
+    ///
+    /// The current item always stays between the items that played and the
+    /// ones that didn't, so moving it doesn't skip items or play them again.
+    /// Moving the current item therefore doesn't change the playback order.
+    pub fn move_item(&mut self, from: usize, to: usize) -> Result<(), QueueError> {
+        let len = self.playback_len();
+        if from >= len || to >= len {
+            return Err(QueueError::IndexOutOfBounds);
+        }
+        let playing = self.current_next_up_item.is_none();
+        if from == to || (playing && self.current_item == Some(from)) {
+            return Ok(());
+        }
+        let state = self.undo_state();
+        let mut order = self.playback_order();
+        let raw_index = order.remove(from);
+        order.insert(to, raw_index);
+        if let Some(ref mut current) = self.current_item {
+            if from < *current {
+                *current -= 1;
+            }
+            if to < *current || (to == *current && playing) {
+                *current += 1;
+            }
+        }
+        self.emit(QueueEvent::ItemsMoved { from, to });
+        let edit = self.set_playback_order(order);
+        self.push_undo(state, edit);
+        Ok(())
+    }
+
+    /// Swap the items at the given indices in the playback order.
+    ///
+    /// The current item keeps its place between the items that played and
+    /// the ones that didn't, see [OldQueue::move_item]. Swapping it with an
+    /// item that didn't play yet makes that item play next, swapping it with
+    /// one that played makes that item the last one that played.
+    pub fn swap(&mut self, a: usize, b: usize) -> Result<(), QueueError> {
+        let len = self.playback_len();
+        if a >= len || b >= len {
+            return Err(QueueError::IndexOutOfBounds);
+        }
+        if a == b {
+            return Ok(());
+        }
+        if let (Some(current), None) = (self.current_item, self.current_next_up_item) {
+            if a == current || b == current {
+                let other = if a == current { b } else { a };
+                let to = if other > current { current + 1 } else { current - 1 };
+                return self.move_item(other, to);
+            }
+        }
+        let state = self.undo_state();
+        let mut order = self.playback_order();
+        order.swap(a, b);
+        // Swapping is moving the last item to the first place, and the item
+        // after it to the last place
+        let (first, last) = (a.min(b), a.max(b));
+        self.emit(QueueEvent::ItemsMoved { from: last, to: first });
+        if first + 1 < last {
+            self.emit(QueueEvent::ItemsMoved { from: first + 1, to: last });
+        }
+        let edit = self.set_playback_order(order);
+        self.push_undo(state, edit);
+        Ok(())
+    }
+
+    /// The playback order as indices in `items`.
+    fn playback_order(&self) -> Vec<usize> {
+        match self.shuffle_order {
+            Some(ref shuffle_indices) => shuffle_indices.clone(),
+            None => (0..self.items.len()).collect(),
+        }
+    }
+
+    /// Use `order` as the playback order. If the queue isn't shuffled, `items`
+    /// is reordered instead, so it stays unshuffled. In that case, `order`
+    /// has to contain every index in `items` exactly once. Returns the edit
+    /// that undoes the changes to the items.
+    fn set_playback_order(&mut self, order: Vec<usize>) -> ItemsEdit<I, C> {
+        if self.shuffle_order.is_some() {
+            self.shuffle_order = Some(order);
+            ItemsEdit::None
+        } else {
+            let new_indices = reorder(&mut self.items, &order);
+            self.remap_history(|raw_index| Some(new_indices[raw_index]));
+            ItemsEdit::Reorder(new_indices)
+        }
+    }
+
+    /// Remove the item at the given index in `items`, together with all the
+    /// places it has in the playback order and the history.
+    fn remove_raw(&mut self, raw_index: usize) -> QueueItem<I, C> {
+        let positions: Vec<usize> = (0..self.playback_len())
+            .filter(|i| self.raw_index(*i) == raw_index)
+            .collect();
+        let item = self.items.remove(raw_index);
+        if let Some(ref mut shuffle_indices) = self.shuffle_order {
+            shuffle_indices.retain(|i| *i != raw_index);
+            for i in shuffle_indices.iter_mut() {
+                if *i > raw_index {
+                    *i -= 1;
+                }
+            }
+        }
+        self.remap_history(|i| match i.cmp(&raw_index) {
+            std::cmp::Ordering::Less => Some(i),
+            std::cmp::Ordering::Equal => None,
+            std::cmp::Ordering::Greater => Some(i - 1),
+        });
+        if let Some(current) = self.current_item {
+            let removed_current = self.current_next_up_item.is_none() && positions.contains(&current);
+            let current = current - positions.iter().filter(|i| **i < current).count();
+            self.current_item = Some(current);
+            if removed_current {
+                // Like with next, the next up items play before the item
+                // after the removed item
+                self.track_index = 0;
+                if self.next_up_index < self.next_up_items.len() {
+                    self.current_next_up_item = Some(self.next_up_index);
+                    self.next_up_index += 1;
+                } else if current >= self.playback_len() {
+                    self.current_item = None;
+                    self.history_index = None;
+                }
+            }
+        }
+        item
+    }
+
+    /// Change the indices in `items` that are in the history. Entries for
+    /// which `map` returns None are removed from the history.
+    fn remap_history<F: Fn(usize) -> Option<usize>>(&mut self, map: F) {
+        let history_index = self.history_index;
+        let mut kept_before_index = 0;
+        let mut history = Vec::with_capacity(self.history.len());
+        for (i, (index, track_index)) in self.history.iter().enumerate() {
+            let index = match *index {
+                ItemIndex::Queue(raw_index) => map(raw_index).map(ItemIndex::Queue),
+                ItemIndex::NextUp(next_up_index) => Some(ItemIndex::NextUp(next_up_index)),
+            };
+            if let Some(index) = index {
+                if history_index.is_some_and(|history_index| i < history_index) {
+                    kept_before_index += 1;
+                }
+                history.push((index, *track_index));
+            }
+        }
+        self.history_index = history_index
+            .map(|_| kept_before_index)
+            .filter(|history_index| *history_index < history.len());
+        self.history = history;
     }
 
     /// Return whether the queue is shuffled.
     #[inline]
     pub fn is_shuffled(&self) -> bool {
-        self.shuffle_order.is_some()
+        self.shuffled
     }
 
     /// (Re)shuffle the queue.
@@ -306,77 +1213,926 @@ impl<I, C: QueueableCollection> OldQueue<I, C> {
     /// \[0, 1, 2, 3, 4, 5]
     /// ----------------^
     pub fn shuffle(&mut self) {
-        if let Some(index) = self.current_item {
+        self.shuffle_with(&Uniform);
+    }
+
+    /// (Re)shuffle the queue like [OldQueue::shuffle], but decide the order
+    /// of the unplayed items with the given algorithm, like
+    /// [Spread](crate::shuffle::Spread). Items that are added later are put
+    /// at a random unplayed place without changing this order. The reshuffle
+    /// of [OldQueue::reshuffle_on_repeat] still shuffles uniformly.
+    ///
+    /// Like with [OldQueue::shuffle], the next up items aren't shuffled, they
+    /// always play in the order they were added. Use [Queue::shuffle_with]
+    /// to spread them apart as well.
+    pub fn shuffle_with<A: ShuffleAlgorithm<QueueItem<I, C>> + ?Sized>(&mut self, algorithm: &A) {
+        let state = self.undo_state();
+        self.shuffle_unplayed(algorithm);
+        self.shuffled = true;
+        self.emit(QueueEvent::Shuffled);
+        self.push_undo(state, ItemsEdit::None);
+    }
+
+    /// Shuffle the items that didn't play yet, see [OldQueue::shuffle].
+    fn shuffle_unplayed<A: ShuffleAlgorithm<QueueItem<I, C>> + ?Sized>(&mut self, algorithm: &A) {
+        let boundary = if self.current_item.is_some() {
             // Playing
-            if index < self.items.len() - 1 {
-                // We should shuffle
-                if let Some(ref mut shuffle_indices) = self.shuffle_order {
-                    // Shuffled
-                    shuffle_indices[index+1..].shuffle(&mut rand::thread_rng());
-                } else {
-                    // Not shuffled
-                    let mut shuffle_indices: Vec<usize> = (0..self.items.len()).collect();
-                    shuffle_indices[index+1..].shuffle(&mut rand::thread_rng());
-                    self.shuffle_order = Some(shuffle_indices);
-                }
-            }
+            self.boundary()
         } else {
-            // Not playing
-            self.shuffle_order = Some(shuffled_vec(self.items.len()));
+            // Not playing, start over from all the items
+            self.shuffle_order = None;
+            0
+        };
+        let items = &self.items;
+        let shuffle_indices = self
+            .shuffle_order
+            .get_or_insert_with(|| (0..items.len()).collect());
+        if boundary >= shuffle_indices.len() {
+            // Everything already played, nothing to shuffle
+            return;
         }
+        shuffle_raw_indices(
+            &mut shuffle_indices[boundary..],
+            |raw_index| &items[raw_index],
+            algorithm,
+            &mut self.rng,
+        );
     }
 
     /// Unshuffle the queue.
     /// See [UnshuffleStrategy] for all the options.
     pub fn unshuffle(&mut self) {
-        if let Some(index) = self.current_item {
+        let state = self.undo_state();
+        if !self.shuffled {
+            return;
+        }
+        if self.current_item.is_some() {
             // Playing
+            let boundary = self.boundary();
             if let Some(ref mut shuffle_indices) = self.shuffle_order {
                 // Shuffled
                 match self.unshuffle_strat {
                     UnshuffleStrategy::PlayUnplayed => {
-                        if index != self.items.len() - 1 {
+                        if boundary < shuffle_indices.len() {
                             // If not at the last item, otherwise shuffling
                             // isn't needed!
-                            shuffle_indices[index+1..].sort();
+                            shuffle_indices[boundary..].sort();
                         }
                     }
                     UnshuffleStrategy::KeepIndex => {
-                        todo!()
+                        let next_raw_index = match boundary {
+                            0 => 0,
+                            _ => shuffle_indices[boundary - 1] + 1,
+                        };
+                        shuffle_indices.truncate(boundary);
+                        shuffle_indices.extend(next_raw_index..self.items.len());
                     }
                     UnshuffleStrategy::KeepRawIndex => {
-                        for i in index+1..self.items.len() {
-                            shuffle_indices[i] = i;
-                        }
+                        shuffle_indices.truncate(boundary);
+                        shuffle_indices.extend(boundary..self.items.len());
                     }
                     UnshuffleStrategy::FromBeginning => {
-                        todo!()
+                        shuffle_indices.truncate(boundary);
+                        shuffle_indices.extend(0..self.items.len());
                     }
                 }
+                if shuffle_indices.iter().enumerate().all(|(i, raw)| i == *raw)
+                    && shuffle_indices.len() == self.items.len()
+                {
+                    // Back in the original order
+                    self.shuffle_order = None;
+                }
             }
         } else {
             // Not playing
             self.shuffle_order = None;
         }
+        self.shuffled = false;
+        self.emit(QueueEvent::Unshuffled);
+        self.push_undo(state, ItemsEdit::None);
+    }
+
+    /// Shuffle either the order of the items or the tracks inside the
+    /// collections, see [ShuffleMode]. Only the items that didn't play yet
+    /// are changed. Undoing this restores the order of the items, but not the
+    /// order inside the collections.
+    pub fn shuffle_in(&mut self, mode: ShuffleMode) {
+        match mode {
+            ShuffleMode::Items => {
+                self.for_each_unplayed_collection(|collection, _| collection.unshuffle());
+                if self.history_index.is_none() {
+                    // Keep playing the current track
+                    let track_index = self.track_index;
+                    if let Ok((index, _)) = self.current_position() {
+                        if let QueueItem::Collection(ref mut collection) = *self.get_item_mut(index) {
+                            collection.unshuffle_after(track_index);
+                        }
+                    }
+                }
+                self.shuffle();
+            }
+            ShuffleMode::Collections => {
+                self.for_each_unplayed_collection(|collection, rng| collection.shuffle(rng));
+                self.emit(QueueEvent::Shuffled);
+            }
+        }
+    }
+
+    /// Call `f` for every collection that didn't start playing yet, in both
+    /// the queue and the next up items, together with the generator of the
+    /// queue.
+    fn for_each_unplayed_collection<F: FnMut(&mut C, &mut QueueRng)>(&mut self, mut f: F) {
+        let current = self.current_position().ok().map(|(index, _)| index);
+        let mut indices: Vec<ItemIndex> = (self.boundary()..self.playback_len())
+            .map(|i| ItemIndex::Queue(self.raw_index(i)))
+            .chain((self.next_up_index..self.next_up_items.len()).map(ItemIndex::NextUp))
+            .filter(|index| Some(*index) != current)
+            .collect();
+        // Items can have more than one place in the playback order, see
+        // UnshuffleStrategy::KeepIndex
+        indices.sort_unstable();
+        indices.dedup();
+        for index in indices {
+            // Not get_item_mut, the generator is borrowed at the same time
+            let item = match index {
+                ItemIndex::Queue(index) => &mut self.items[index],
+                ItemIndex::NextUp(index) => &mut self.next_up_items[index],
+            };
+            if let QueueItem::Collection(ref mut collection) = *item {
+                f(collection, &mut self.rng);
+            }
+        }
+    }
+
+    /// Toggle shuffle.
+    pub fn toggle_shuffle(&mut self) {
+        if self.shuffled {
+            self.unshuffle();
+        } else {
+            self.shuffle();
+        }
     }
 
-    /// Toggle shuffle.
-    pub fn toggle_shuffle(&mut self) {
-        if self.shuffle_order.is_some() {
-            self.unshuffle();
-        } else {
-            self.shuffle();
-        }
-    }
-
     #[inline]
     pub fn is_playing(&self) -> bool {
         self.current_item.is_some()
     }
+
+    /// Undo the last edit of the queue, like [OldQueue::queue],
+    /// [OldQueue::clear], [OldQueue::shuffle] or [OldQueue::remove]. The
+    /// items, the playback order, the current item and the history are
+    /// restored to exactly what they were before the edit, so everything
+    /// that played since then is forgotten.
+    pub fn undo(&mut self) -> Result<(), QueueError> {
+        let entry = self.undo_stack.pop_back().ok_or(QueueError::NothingToUndo)?;
+        let entry = self.revert(entry);
+        self.redo_stack.push(entry);
+        self.emit(QueueEvent::Restored);
+        Ok(())
+    }
+
+    /// Redo the last edit that was undone with [OldQueue::undo]. Making a
+    /// new edit forgets all the edits that can be redone.
+    pub fn redo(&mut self) -> Result<(), QueueError> {
+        let entry = self.redo_stack.pop().ok_or(QueueError::NothingToRedo)?;
+        let entry = self.revert(entry);
+        self.undo_stack.push_back(entry);
+        self.emit(QueueEvent::Restored);
+        Ok(())
+    }
+
+    /// Return whether there is an edit that can be undone.
+    #[inline]
+    pub fn can_undo(&self) -> bool {
+        !self.undo_stack.is_empty()
+    }
+
+    /// Return whether there is an edit that can be redone.
+    #[inline]
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+
+    /// The state that is needed to undo an edit, next to the items.
+    fn undo_state(&self) -> UndoState {
+        UndoState {
+            history: self.history.clone(),
+            history_index: self.history_index,
+            shuffle_order: self.shuffle_order.clone(),
+            shuffled: self.shuffled,
+            current_next_up_item: self.current_next_up_item,
+            next_up_index: self.next_up_index,
+            current_item: self.current_item,
+            track_index: self.track_index,
+        }
+    }
+
+    /// Add an edit to the undo stack, given the state from before the edit
+    /// and the change to the items that undoes it.
+    fn push_undo(&mut self, state: UndoState, edit: ItemsEdit<I, C>) {
+        if edit.is_empty() && state == self.undo_state() {
+            // Nothing changed, so there is nothing to undo
+            return;
+        }
+        self.redo_stack.clear();
+        if self.undo_depth == 0 {
+            return;
+        }
+        if self.undo_stack.len() == self.undo_depth {
+            self.undo_stack.pop_front();
+        }
+        self.undo_stack.push_back(UndoEntry { state, edit });
+    }
+
+    /// Go back to the state in `entry` and return the entry that goes back
+    /// to the current state.
+    fn revert(&mut self, entry: UndoEntry<I, C>) -> UndoEntry<I, C> {
+        let state = self.undo_state();
+        let edit = match entry.edit {
+            ItemsEdit::None => ItemsEdit::None,
+            ItemsEdit::Insert { items, next_up_items } => ItemsEdit::Remove {
+                items: insert_indices(&mut self.items, items),
+                next_up_items: insert_indices(&mut self.next_up_items, next_up_items),
+            },
+            ItemsEdit::Remove { items, next_up_items } => ItemsEdit::Insert {
+                items: remove_indices(&mut self.items, items),
+                next_up_items: remove_indices(&mut self.next_up_items, next_up_items),
+            },
+            ItemsEdit::Reorder(order) => ItemsEdit::Reorder(reorder(&mut self.items, &order)),
+        };
+        let UndoState {
+            history,
+            history_index,
+            shuffle_order,
+            shuffled,
+            current_next_up_item,
+            next_up_index,
+            current_item,
+            track_index,
+        } = entry.state;
+        self.history = history;
+        self.history_index = history_index;
+        self.shuffle_order = shuffle_order;
+        self.shuffled = shuffled;
+        self.current_next_up_item = current_next_up_item;
+        self.next_up_index = next_up_index;
+        self.current_item = current_item;
+        self.track_index = track_index;
+        UndoEntry { state, edit }
+    }
 }
 
-/// The mode that is used to repeat the queue playback.
+impl<I, C: QueueableCollection> From<Vec<QueueItem<I, C>>> for Queue<I, C> {
+    fn from(items: Vec<QueueItem<I, C>>) -> Self {
+        let seed = rand::random();
+        Queue {
+            index: if items.is_empty() { None } else { Some(0) },
+            queue: items.into_iter().map(Arc::new).collect(),
+            order: None,
+            short_term_index: None,
+            short_term_queue: Vec::new(),
+            short_term_order: None,
+            shuffled: false,
+            playing_short_term: false,
+            history: Vec::new(),
+            repeat_status: None,
+            seed,
+            rng: seeded_rng(seed),
+        }
+    }
+}
+
+impl<I, C: QueueableCollection> Default for Queue<I, C> {
+    fn default() -> Self {
+        let seed = rand::random();
+        Self {
+            index: None,
+            queue: Vec::new(),
+            order: None,
+            short_term_index: None,
+            short_term_queue: Vec::new(),
+            short_term_order: None,
+            shuffled: false,
+            playing_short_term: false,
+            history: Vec::new(),
+            repeat_status: None,
+            seed,
+            rng: seeded_rng(seed),
+        }
+    }
+}
+
+impl<I, C: QueueableCollection> Queue<I, C> {
+    /// Change the current item to the next one. Items from the short term
+    /// queue are always played before the next item of the normal queue.
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> Result<(), QueueError> {
+        let current = self.current_arc().ok_or(QueueError::NotPlaying)?;
+        if matches!(self.repeat_status, Some(RepeatMode::Item) | Some(RepeatMode::Container)) {
+            // Collections are played as one unit, so repeating the container
+            // is the same as repeating the item.
+            self.history.push(current);
+            return Ok(());
+        }
+        if let Some(short_term_index) = self.short_term_index {
+            if short_term_index < self.short_term_queue.len() {
+                // Next up is an item from the short term queue
+                if !self.playing_short_term {
+                    // Remember where to continue in the normal queue
+                    if let Some(ref mut index) = self.index {
+                        *index += 1;
+                    }
+                    self.playing_short_term = true;
+                }
+                self.short_term_index = Some(short_term_index + 1);
+                self.history.push(current);
+                return Ok(());
+            }
+        }
+        let next_index = if self.playing_short_term {
+            self.index
+        } else {
+            self.index.map(|index| index + 1)
+        };
+        match next_index {
+            Some(index) if index < self.queue.len() => {
+                self.index = Some(index);
+            }
+            _ if matches!(self.repeat_status, Some(RepeatMode::All)) && !self.queue.is_empty() => {
+                self.index = Some(0);
+                if !self.shuffled {
+                    // Unshuffled while playing, start over in the original
+                    // order
+                    self.order = None;
+                }
+            }
+            _ => return Err(QueueError::ReachedEnd),
+        }
+        self.playing_short_term = false;
+        self.history.push(current);
+        Ok(())
+    }
+
+    /// Change the current item to the previous one.
+    ///
+    /// Going back from the first item of the short term queue returns to the
+    /// item of the normal queue that played before it. Going back in the
+    /// normal queue skips the short term items that already played.
+    pub fn previous(&mut self) -> Result<(), QueueError> {
+        if self.playing_short_term {
+            // Playing from the short term queue, so short_term_index >= 1
+            let short_term_index = self.short_term_index.unwrap_or(1);
+            if short_term_index > 1 {
+                self.short_term_index = Some(short_term_index - 1);
+                Ok(())
+            } else {
+                match self.index {
+                    Some(index) if index > 0 => {
+                        self.index = Some(index - 1);
+                        self.short_term_index = Some(0);
+                        self.playing_short_term = false;
+                        Ok(())
+                    }
+                    _ => Err(QueueError::ReachedBeginning),
+                }
+            }
+        } else {
+            match self.index {
+                Some(index) if index > 0 => {
+                    self.index = Some(index - 1);
+                    Ok(())
+                }
+                Some(_) => Err(QueueError::ReachedBeginning),
+                None => Err(QueueError::NotPlaying),
+            }
+        }
+    }
+
+    /// Start playing the queue if it's stopped. The queue starts playing as
+    /// soon as an item is added, so this only returns
+    /// [QueueError::EmptyQueue] if there is nothing to play.
+    pub fn play(&mut self) -> Result<(), QueueError> {
+        if self.is_playing() {
+            Ok(())
+        } else {
+            Err(QueueError::EmptyQueue)
+        }
+    }
+
+    /// Gets the currently playing item.
+    pub fn current(&self) -> Result<&QueueItem<I, C>, QueueError> {
+        if self.playing_short_term {
+            let index = self.short_term_index.unwrap_or(1) - 1;
+            Ok(&self.short_term_queue[Self::raw_index(&self.short_term_order, index)])
+        } else if let Some(index) = self.index {
+            Ok(&self.queue[Self::raw_index(&self.order, index)])
+        } else {
+            Err(QueueError::NotPlaying)
+        }
+    }
+
+    /// Get the items that were played with this queue, oldest first.
+    pub fn history(&self) -> Vec<&QueueItem<I, C>> {
+        self.history.iter().map(|item| item.as_ref()).collect()
+    }
+
+    /// Get the current item followed by the items that play after it, in
+    /// playback order. Items that already played aren't included.
+    pub fn upcoming(&self) -> Vec<&QueueItem<I, C>> {
+        let mut items: Vec<&QueueItem<I, C>> = self.current().into_iter().collect();
+        if let Some(short_term_index) = self.short_term_index {
+            items.extend((short_term_index..self.short_term_queue.len()).map(|index| {
+                self.short_term_queue[Self::raw_index(&self.short_term_order, index)].as_ref()
+            }));
+        }
+        items.extend((self.boundary()..self.queue.len()).map(|index| {
+            self.queue[Self::raw_index(&self.order, index)].as_ref()
+        }));
+        items
+    }
+
+    /// Get the next `n` items that play with [Queue::next], without changing
+    /// the queue. This takes into account the short term queue, the shuffle
+    /// order and the repeat mode, so the items can be prepared before they
+    /// play. With [RepeatMode::All], the items after the end of the queue are
+    /// included.
+    pub fn peek_next(&self, n: usize) -> Vec<&QueueItem<I, C>> {
+        self.peek_arcs(n).into_iter().map(|item| item.as_ref()).collect()
+    }
+
+    /// Add an item to the end of the queue. If the queue is shuffled, the
+    /// item is put at a random place in the unplayed part of the queue.
+    pub fn queue(&mut self, item: QueueItem<I, C>) {
+        let boundary = self.boundary();
+        let raw_index = self.queue.len();
+        self.queue.push(Arc::new(item));
+        if let Some(ref mut order) = self.order {
+            let position = if self.shuffled {
+                self.rng.gen_range(boundary..=order.len())
+            } else {
+                order.len()
+            };
+            order.insert(position, raw_index);
+        }
+        if self.index.is_none() {
+            self.index = Some(0);
+        }
+    }
+
+    /// Add an item to the short term queue. It will play after the current
+    /// item and the items that were already added with `queue_next`.
+    pub fn queue_next(&mut self, item: QueueItem<I, C>) {
+        let short_term_index = self.short_term_index.unwrap_or(0);
+        let raw_index = self.short_term_queue.len();
+        self.short_term_queue.push(Arc::new(item));
+        if let Some(ref mut order) = self.short_term_order {
+            let position = if self.shuffled {
+                self.rng.gen_range(short_term_index..=order.len())
+            } else {
+                order.len()
+            };
+            order.insert(position, raw_index);
+        }
+        if self.is_playing() {
+            self.short_term_index = Some(short_term_index);
+        } else {
+            // Nothing to wait for, start playing right away
+            self.short_term_index = Some(short_term_index + 1);
+            self.playing_short_term = true;
+        }
+    }
+
+    /// Clear both the normal and the short term queue. The history is kept,
+    /// and the queue stays shuffled, so items that are added later are put at
+    /// a random place.
+    pub fn clear(&mut self) {
+        self.index = None;
+        self.queue.clear();
+        self.order = self.shuffled.then(Vec::new);
+        self.short_term_index = None;
+        self.short_term_queue.clear();
+        self.short_term_order = self.shuffled.then(Vec::new);
+        self.playing_short_term = false;
+    }
+
+    /// (Re)shuffle the unplayed items of both the normal and the short term
+    /// queue. The items that already played keep their place.
+    pub fn shuffle(&mut self) {
+        self.shuffle_with(&Uniform);
+    }
+
+    /// (Re)shuffle the queue like [Queue::shuffle], but decide the order of
+    /// the unplayed items of both the normal and the short term queue with
+    /// the given algorithm, like [Spread](crate::shuffle::Spread). Items that
+    /// are added later are still put at a random place.
+    pub fn shuffle_with<A: ShuffleAlgorithm<QueueItem<I, C>> + ?Sized>(&mut self, algorithm: &A) {
+        let boundary = self.boundary();
+        let mut order = self
+            .order
+            .take()
+            .unwrap_or_else(|| (0..self.queue.len()).collect());
+        shuffle_raw_indices(
+            &mut order[boundary..],
+            |raw_index| &self.queue[raw_index],
+            algorithm,
+            &mut self.rng,
+        );
+        self.order = Some(order);
+
+        let short_term_index = self.short_term_index.unwrap_or(0);
+        let mut short_term_order = self
+            .short_term_order
+            .take()
+            .unwrap_or_else(|| (0..self.short_term_queue.len()).collect());
+        shuffle_raw_indices(
+            &mut short_term_order[short_term_index..],
+            |raw_index| &self.short_term_queue[raw_index],
+            algorithm,
+            &mut self.rng,
+        );
+        self.short_term_order = Some(short_term_order);
+        self.shuffled = true;
+    }
+
+    /// Shuffle either the order of the items or the tracks inside the
+    /// collections, see [ShuffleMode]. Only the items of both the normal and
+    /// the short term queue that didn't play yet are changed. Collections
+    /// that are shared, like the ones in the history, are copied first, so
+    /// the history doesn't change.
+    pub fn shuffle_in(&mut self, mode: ShuffleMode)
+    where
+        I: Clone,
+        C: Clone,
+    {
+        match mode {
+            ShuffleMode::Items => {
+                self.for_each_unplayed_collection(|collection, _| collection.unshuffle());
+                self.shuffle();
+            }
+            ShuffleMode::Collections => {
+                self.for_each_unplayed_collection(|collection, rng| collection.shuffle(rng))
+            }
+        }
+    }
+
+    /// Call `f` for every collection in the normal and the short term queue
+    /// that didn't play yet, together with the generator of the queue.
+    fn for_each_unplayed_collection<F: FnMut(&mut C, &mut QueueRng)>(&mut self, mut f: F)
+    where
+        I: Clone,
+        C: Clone,
+    {
+        let boundary = self.boundary();
+        let short_term_index = self.short_term_index.unwrap_or(0);
+        let mut queue_indices: Vec<usize> = match self.order {
+            Some(ref order) => order[boundary..].to_vec(),
+            None => (boundary..self.queue.len()).collect(),
+        };
+        let mut short_term_indices: Vec<usize> = match self.short_term_order {
+            Some(ref order) => order[short_term_index..].to_vec(),
+            None => (short_term_index..self.short_term_queue.len()).collect(),
+        };
+        queue_indices.sort_unstable();
+        short_term_indices.sort_unstable();
+        for raw_index in queue_indices {
+            if let QueueItem::Collection(collection) = Arc::make_mut(&mut self.queue[raw_index]) {
+                f(collection, &mut self.rng);
+            }
+        }
+        for raw_index in short_term_indices {
+            if let QueueItem::Collection(collection) = Arc::make_mut(&mut self.short_term_queue[raw_index]) {
+                f(collection, &mut self.rng);
+            }
+        }
+    }
+
+    /// Return whether the normal or the short term queue is shuffled.
+    #[inline]
+    pub fn is_shuffled(&self) -> bool {
+        self.shuffled
+    }
+
+    /// Unshuffle the queue. The unplayed items of both the normal and the
+    /// short term queue are put back in the order they were added, like
+    /// [UnshuffleStrategy::PlayUnplayed].
+    pub fn unshuffle(&mut self) {
+        if !self.shuffled {
+            return;
+        }
+        let boundary = self.boundary();
+        Self::unshuffle_order(&mut self.order, boundary);
+        let short_term_index = self.short_term_index.unwrap_or(0);
+        Self::unshuffle_order(&mut self.short_term_order, short_term_index);
+        self.shuffled = false;
+    }
+
+    /// Use the given seed for shuffling the queue.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.set_seed(seed);
+        self
+    }
+
+    /// Get the seed that is used for shuffling the queue.
+    #[inline]
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Restart shuffling from the given seed. Shuffling the same items with
+    /// the same seed always results in the same playback order.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.seed = seed;
+        self.rng = seeded_rng(seed);
+    }
+
+    /// Get the repeat mode of the queue.
+    #[inline]
+    pub fn repeat_status(&self) -> Option<RepeatMode> {
+        self.repeat_status
+    }
+
+    /// Set the repeat mode of the queue.
+    #[inline]
+    pub fn set_repeat_status(&mut self, repeat_status: Option<RepeatMode>) {
+        self.repeat_status = repeat_status;
+    }
+
+    /// The amount of items in both the normal and the short term queue.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.queue.len() + self.short_term_queue.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    #[inline]
+    pub fn is_playing(&self) -> bool {
+        self.playing_short_term || self.index.is_some()
+    }
+
+    /// Get the currently playing item, shared with the queue.
+    pub(crate) fn current_arc(&self) -> Option<Arc<QueueItem<I, C>>> {
+        self.current_arc_ref().cloned()
+    }
+
+    fn current_arc_ref(&self) -> Option<&Arc<QueueItem<I, C>>> {
+        if self.playing_short_term {
+            let index = self.short_term_index.unwrap_or(1) - 1;
+            Some(&self.short_term_queue[Self::raw_index(&self.short_term_order, index)])
+        } else {
+            self.index.map(|index| &self.queue[Self::raw_index(&self.order, index)])
+        }
+    }
+
+    /// Get the item that plays after the current one with [Queue::next],
+    /// shared with the queue. Returns None if the queue reaches the end.
+    pub(crate) fn next_arc(&self) -> Option<Arc<QueueItem<I, C>>> {
+        self.peek_arcs(1).pop().cloned()
+    }
+
+    /// The items that play with the next `n` calls of [Queue::next], see
+    /// [Queue::peek_next].
+    fn peek_arcs(&self, n: usize) -> Vec<&Arc<QueueItem<I, C>>> {
+        let mut items = Vec::with_capacity(n);
+        let Some(current) = self.current_arc_ref() else {
+            return items;
+        };
+        if matches!(self.repeat_status, Some(RepeatMode::Item) | Some(RepeatMode::Container)) {
+            items.resize(n, current);
+            return items;
+        }
+        let short_term_index = self.short_term_index.unwrap_or(0);
+        items.extend(
+            (short_term_index..self.short_term_queue.len())
+                .take(n)
+                .map(|index| &self.short_term_queue[Self::raw_index(&self.short_term_order, index)]),
+        );
+        let mut index = self.boundary();
+        let mut order = &self.order;
+        while items.len() < n {
+            if index >= self.queue.len() {
+                if matches!(self.repeat_status, Some(RepeatMode::All)) && !self.queue.is_empty() {
+                    index = 0;
+                    if !self.shuffled {
+                        order = &None;
+                    }
+                } else {
+                    break;
+                }
+            }
+            items.push(&self.queue[Self::raw_index(order, index)]);
+            index += 1;
+        }
+        items
+    }
+
+    /// The first index in `order` of an item that didn't play yet.
+    fn boundary(&self) -> usize {
+        match self.index {
+            Some(index) if !self.playing_short_term => index + 1,
+            Some(index) => index,
+            None => 0,
+        }
+    }
+
+    /// Translate an index in the playback order to an index in the queue.
+    fn raw_index(order: &Option<Vec<usize>>, index: usize) -> usize {
+        match order {
+            Some(order) => order[index],
+            None => index,
+        }
+    }
+
+    /// Sort the part of `order` starting from `boundary`, and drop the order
+    /// completely if that makes it the same as the original order.
+    fn unshuffle_order(order: &mut Option<Vec<usize>>, boundary: usize) {
+        if let Some(ref mut indices) = order {
+            if boundary < indices.len() {
+                indices[boundary..].sort();
+            }
+            if indices.iter().enumerate().all(|(i, raw)| i == *raw) {
+                *order = None;
+            }
+        }
+    }
+}
+
+impl<I, C: QueueableCollection<Item = I>> OldQueue<I, C> {
+    /// Gets the currently playing track. For single items this is the item
+    /// itself, for collections it's the track inside the collection.
+    pub fn current_track(&self) -> Result<&I, QueueError> {
+        let (index, track_index) = self.current_position()?;
+        match *self.get_item(index) {
+            QueueItem::Single(ref item) => Ok(item),
+            QueueItem::Collection(ref collection) if collection.is_empty() => {
+                Err(QueueError::EmptyCollection)
+            }
+            QueueItem::Collection(ref collection) => collection.get_at_index(track_index),
+        }
+    }
+}
+
+/// A track that plays later, see [OldQueue::peek_next].
+#[derive(Debug)]
+pub struct UpcomingTrack<'a, I, C: QueueableCollection> {
+    /// The item the track belongs to.
+    pub item: &'a QueueItem<I, C>,
+    /// The index of the track inside `item`, taking into account the shuffle
+    /// status of the collection. Always 0 for single items.
+    pub track_index: usize,
+}
+
+impl<'a, I, C: QueueableCollection<Item = I>> UpcomingTrack<'a, I, C> {
+    /// Get the track itself.
+    pub fn track(&self) -> Result<&'a I, QueueError> {
+        match self.item {
+            QueueItem::Single(item) => Ok(item),
+            QueueItem::Collection(collection) if collection.is_empty() => {
+                Err(QueueError::EmptyCollection)
+            }
+            QueueItem::Collection(collection) => collection.get_at_index(self.track_index),
+        }
+    }
+}
+
+/// A view of all the items in an [OldQueue], in playback order. It can be
+/// indexed and iterated as if all the parts were one list.
+#[derive(Debug)]
+pub struct QueueView<'a, I, C: QueueableCollection> {
+    /// The items that already played, oldest first. A collection is in here
+    /// once for every track of it that played.
+    pub history: Vec<&'a QueueItem<I, C>>,
+    /// If the user went back, the index in `history` of the item that is
+    /// playing. `current` is then the item that plays after catching back up.
+    pub history_index: Option<usize>,
+    /// The currently playing item, or None if the queue is stopped.
+    pub current: Option<&'a QueueItem<I, C>>,
+    /// The items added with [OldQueue::queue_next] that didn't play yet.
+    pub up_next: Vec<&'a QueueItem<I, C>>,
+    /// The rest of the queue that didn't play yet.
+    pub remaining: Vec<&'a QueueItem<I, C>>,
+}
+
+impl<'a, I, C: QueueableCollection> QueueView<'a, I, C> {
+    /// Iterate over all the parts of the view, in playback order.
+    pub fn iter(&self) -> impl Iterator<Item = &'a QueueItem<I, C>> + '_ {
+        self.history
+            .iter()
+            .chain(self.current.iter())
+            .chain(self.up_next.iter())
+            .chain(self.remaining.iter())
+            .copied()
+    }
+
+    /// The amount of items in all the parts of the view.
+    pub fn len(&self) -> usize {
+        self.history.len() + self.current.iter().len() + self.up_next.len() + self.remaining.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get the item at the given index, as if all the parts were one list.
+    pub fn get(&self, index: usize) -> Result<&'a QueueItem<I, C>, QueueError> {
+        self.iter().nth(index).ok_or(QueueError::IndexOutOfBounds)
+    }
+}
+
+impl<'a, I, C: QueueableCollection> Index<usize> for QueueView<'a, I, C> {
+    type Output = QueueItem<I, C>;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        self.get(index).expect("index out of bounds")
+    }
+}
+
+/// The index of an item in one of the parts of an [OldQueue].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+enum ItemIndex {
+    /// An index in `items`.
+    Queue(usize),
+    /// An index in `next_up_items`.
+    NextUp(usize),
+}
+
+/// An edit of an [OldQueue] that can be undone.
+#[derive(Clone, Debug)]
+struct UndoEntry<I, C: QueueableCollection> {
+    /// The state from before the edit.
+    state: UndoState,
+    /// The change to the items that undoes the edit.
+    edit: ItemsEdit<I, C>,
+}
+
+/// The part of the state of an [OldQueue] that is restored by undoing an
+/// edit, next to the items themselves.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct UndoState {
+    history: Vec<(ItemIndex, usize)>,
+    history_index: Option<usize>,
+    shuffle_order: Option<Vec<usize>>,
+    shuffled: bool,
+    current_next_up_item: Option<usize>,
+    next_up_index: usize,
+    current_item: Option<usize>,
+    track_index: usize,
+}
+
+/// A change to the items of an [OldQueue]. Applying it results in the change
+/// that reverts it.
 #[derive(Clone, Debug)]
+enum ItemsEdit<I, C: QueueableCollection> {
+    /// The items don't change.
+    None,
+    /// Insert the items at the given indices in `items` and `next_up_items`,
+    /// in ascending order.
+    Insert {
+        items: Vec<(usize, QueueItem<I, C>)>,
+        next_up_items: Vec<(usize, QueueItem<I, C>)>,
+    },
+    /// Remove the items at the given indices in `items` and `next_up_items`,
+    /// in ascending order.
+    Remove {
+        items: Vec<usize>,
+        next_up_items: Vec<usize>,
+    },
+    /// Reorder `items`, so the item at index i comes from index `order[i]`.
+    Reorder(Vec<usize>),
+}
+
+impl<I, C: QueueableCollection> ItemsEdit<I, C> {
+    /// Return whether applying the edit doesn't change the items.
+    fn is_empty(&self) -> bool {
+        match self {
+            ItemsEdit::None => true,
+            ItemsEdit::Insert { items, next_up_items } => items.is_empty() && next_up_items.is_empty(),
+            ItemsEdit::Remove { items, next_up_items } => items.is_empty() && next_up_items.is_empty(),
+            ItemsEdit::Reorder(order) => order.iter().enumerate().all(|(i, index)| i == *index),
+        }
+    }
+}
+
+/// What is shuffled by [OldQueue::shuffle_in] and [Queue::shuffle_in].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum ShuffleMode {
+    /// Shuffle the order of the items, but play the tracks of every
+    /// collection in their original order, like shuffling a list of albums
+    /// without breaking them up.
+    Items,
+    /// Keep the order of the items, but shuffle the tracks inside every
+    /// collection with [QueueableCollection::shuffle].
+    Collections,
+}
+
+/// The mode that is used to repeat the queue playback.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum RepeatMode {
     /// Repeat all the items in the queue when the queue reaches the end.
     All,
@@ -388,7 +2144,31 @@ pub enum RepeatMode {
     Item,
 }
 
-#[derive(Clone, Debug)]
+/// What happens to the items that are skipped by [OldQueue::jump_to].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum JumpPolicy {
+    /// The skipped items are added to the history, as if they played.
+    ///
+    /// \[0, 1, 2, 3, 4, 5]
+    /// ----^
+    /// jumping to 4 becomes
+    /// \[0, 1, 2, 3, 4, 5]
+    /// -------------^
+    AddToHistory,
+    /// The skipped items stay unplayed, and play after the new item.
+    ///
+    /// \[0, 1, 2, 3, 4, 5]
+    /// ----^
+    /// jumping to 4 becomes
+    /// \[0, 1, 4, 2, 3, 5]
+    /// -------^
+    KeepUnplayed,
+}
+
+/// The strategy that is used to continue playback when a playing queue is
+/// unshuffled.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum UnshuffleStrategy {
     /// Order all the unplayed songs in order. This doesn't preserve the
     /// original order, so songs might play out of order from how they were
@@ -432,7 +2212,7 @@ pub enum UnshuffleStrategy {
 }
 
 /// Errors specific to the Queue.
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum QueueError {
     /// Reached the beginning of the queue, can't go to the previous item.
     ReachedBeginning,
@@ -440,59 +2220,127 @@ pub enum QueueError {
     ReachedEnd,
     /// The queue isn't playing; the current_item isn't set.
     NotPlaying,
+    /// The given index doesn't point to an item.
+    IndexOutOfBounds,
+    /// The queue doesn't contain any items.
+    EmptyQueue,
+    /// The collection doesn't contain any items.
+    EmptyCollection,
+    /// There is no edit that can be undone.
+    NothingToUndo,
+    /// There is no edit that can be redone.
+    NothingToRedo,
+}
+
+impl fmt::Display for QueueError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QueueError::ReachedBeginning => write!(f, "reached the beginning of the queue"),
+            QueueError::ReachedEnd => write!(f, "reached the end of the queue"),
+            QueueError::NotPlaying => write!(f, "the queue isn't playing"),
+            QueueError::IndexOutOfBounds => write!(f, "index out of bounds"),
+            QueueError::EmptyQueue => write!(f, "the queue is empty"),
+            QueueError::EmptyCollection => write!(f, "the collection is empty"),
+            QueueError::NothingToUndo => write!(f, "there is nothing to undo"),
+            QueueError::NothingToRedo => write!(f, "there is nothing to redo"),
+        }
+    }
 }
 
+impl Error for QueueError {}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::event::{QueueEvent, QueuePart};
+    use crate::item::SimpleCollection;
+    use rand::RngCore;
 
     #[derive(Debug)]
-    pub struct Album {}
+    #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+    pub struct Album {
+        pub tracks: SimpleCollection<SingleItem>,
+    }
 
     #[derive(Debug)]
-    pub struct Playlist {}
+    #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+    pub struct Playlist {
+        pub tracks: SimpleCollection<SingleItem>,
+    }
 
     #[derive(Debug)]
+    #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
     pub struct Track {
         pub id: u32,
     }
 
     #[derive(Debug)]
+    #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
     pub struct Episode {
         pub id: u32,
     }
 
     #[derive(Debug)]
+    #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
     pub enum CollectionItem {
         Album(Album),
         Playlist(Playlist),
     }
 
+    impl CollectionItem {
+        fn tracks(&self) -> &SimpleCollection<SingleItem> {
+            match self {
+                CollectionItem::Album(album) => &album.tracks,
+                CollectionItem::Playlist(playlist) => &playlist.tracks,
+            }
+        }
+
+        fn tracks_mut(&mut self) -> &mut SimpleCollection<SingleItem> {
+            match self {
+                CollectionItem::Album(album) => &mut album.tracks,
+                CollectionItem::Playlist(playlist) => &mut playlist.tracks,
+            }
+        }
+    }
+
     impl QueueableCollection for CollectionItem {
-        type Item = CollectionItem;
+        type Item = SingleItem;
+
+        fn get_at_index(&self, index: usize) -> Result<&Self::Item, QueueError> {
+            self.tracks().get_at_index(index)
+        }
+
+        fn get_at_index_raw(&self, index: usize) -> Result<&Self::Item, QueueError> {
+            self.tracks().get_at_index_raw(index)
+        }
 
-        fn get_at_index(&self, index: usize) -> &Self::Item {
-            todo!()
+        fn len(&self) -> usize {
+            self.tracks().len()
         }
 
-        fn get_at_index_raw(&self, index: usize) -> &Self::Item {
-            todo!()
+        fn is_shuffled(&self) -> bool {
+            self.tracks().is_shuffled()
         }
 
-        fn shuffle(&mut self) {
-            todo!()
+        fn shuffle(&mut self, rng: &mut dyn RngCore) {
+            self.tracks_mut().shuffle(rng)
         }
 
         fn unshuffle(&mut self) {
-            todo!()
+            self.tracks_mut().unshuffle()
+        }
+
+        fn unshuffle_after(&mut self, index: usize) {
+            self.tracks_mut().unshuffle_after(index)
         }
 
-        fn toggle_shuffle(&mut self) {
-            todo!()
+        fn toggle_shuffle(&mut self, rng: &mut dyn RngCore) {
+            self.tracks_mut().toggle_shuffle(rng)
         }
     }
 
     #[derive(Debug)]
+    #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
     pub enum SingleItem {
         Track(Track),
         Episode(Episode),
@@ -566,6 +2414,7 @@ mod tests {
             QueueItem::Single(SingleItem::Track(Track {id: 4})),
         ]);
         queue.shuffle_order = Some(vec![2, 3, 0, 1]);
+        queue.shuffled = true;
 
         assert!(matches!(
             queue.get_current_item(),
@@ -641,6 +2490,8 @@ mod tests {
         ]);
 
         queue.shuffle_order = Some(vec![5, 2, 7, 1, 0, 3, 4, 6]);
+
+        queue.shuffled = true;
         queue.unshuffle();
         assert_eq!(queue.shuffle_order, Some(vec![5, 0, 1, 2, 3, 4, 6, 7]));
 
@@ -708,6 +2559,8 @@ mod tests {
 
         queue.shuffle_order = Some(vec![3, 1, 7, 2, 6, 4, 5, 0]);
 
+        queue.shuffled = true;
+
         assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 3})))));
 
         queue.next().unwrap();
@@ -742,6 +2595,8 @@ mod tests {
         ]);
 
         queue.shuffle_order = Some(vec![3, 1, 7, 2, 6, 4, 5, 0]);
+
+        queue.shuffled = true;
         queue.unshuffle_strat = UnshuffleStrategy::KeepRawIndex;
 
         queue.unshuffle();
@@ -771,6 +2626,8 @@ mod tests {
         ]);
 
         queue.shuffle_order = Some(vec![3, 1, 7, 2, 6, 4, 5, 0]);
+
+        queue.shuffled = true;
         queue.unshuffle_strat = UnshuffleStrategy::KeepRawIndex;
 
         queue.next().unwrap();
@@ -806,6 +2663,8 @@ mod tests {
         ]);
 
         queue.shuffle_order = Some(vec![3, 1, 7, 2, 6, 4, 5, 0]);
+
+        queue.shuffled = true;
         queue.unshuffle_strat = UnshuffleStrategy::KeepRawIndex;
 
         queue.next().unwrap();
@@ -825,23 +2684,32 @@ mod tests {
     }
 
     #[test]
-    fn get_items_single_items_simple() {
-        let queue: OldQueue<SingleItem, CollectionItem> = OldQueue::from(vec![
+    fn unshuffle_strat_keep_index_start() {
+        let mut queue: OldQueue<SingleItem, CollectionItem> = OldQueue::from(vec![
             QueueItem::Single(SingleItem::Track(Track {id: 0})),
             QueueItem::Single(SingleItem::Track(Track {id: 1})),
             QueueItem::Single(SingleItem::Track(Track {id: 2})),
             QueueItem::Single(SingleItem::Track(Track {id: 3})),
+            QueueItem::Single(SingleItem::Track(Track {id: 4})),
+            QueueItem::Single(SingleItem::Track(Track {id: 5})),
+            QueueItem::Single(SingleItem::Track(Track {id: 6})),
+            QueueItem::Single(SingleItem::Track(Track {id: 7})),
         ]);
 
+        queue.shuffle_order = Some(vec![7, 3, 5, 1, 2, 0, 4, 6]);
 
-        assert!(matches!(queue.get_items()[0], QueueItem::Single(SingleItem::Track(Track {id: 0}))));
-        assert!(matches!(queue.get_items()[1], QueueItem::Single(SingleItem::Track(Track {id: 1}))));
-        assert!(matches!(queue.get_items()[2], QueueItem::Single(SingleItem::Track(Track {id: 2}))));
-        assert!(matches!(queue.get_items()[3], QueueItem::Single(SingleItem::Track(Track {id: 3}))));
+        queue.shuffled = true;
+        queue.unshuffle_strat = UnshuffleStrategy::KeepIndex;
+
+        queue.unshuffle();
+
+        assert_eq!(queue.shuffle_order, Some(vec![7]));
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 7})))));
+        assert!(matches!(queue.next(), Err(QueueError::ReachedEnd)));
     }
 
     #[test]
-    fn get_items_shuffled_playing_start() {
+    fn unshuffle_strat_keep_index_middle() {
         let mut queue: OldQueue<SingleItem, CollectionItem> = OldQueue::from(vec![
             QueueItem::Single(SingleItem::Track(Track {id: 0})),
             QueueItem::Single(SingleItem::Track(Track {id: 1})),
@@ -853,17 +2721,26 @@ mod tests {
             QueueItem::Single(SingleItem::Track(Track {id: 7})),
         ]);
 
-        queue.shuffle_order = Some(vec![3, 1, 7, 2, 6, 4, 5, 0]);
+        queue.shuffle_order = Some(vec![7, 3, 5, 1, 2, 0, 4, 6]);
 
-        assert!(matches!(queue.get_items()[0], QueueItem::Single(SingleItem::Track(Track {id: 3}))));
-        assert!(matches!(queue.get_items()[1], QueueItem::Single(SingleItem::Track(Track {id: 1}))));
-        assert!(matches!(queue.get_items()[2], QueueItem::Single(SingleItem::Track(Track {id: 7}))));
-        assert!(matches!(queue.get_items()[3], QueueItem::Single(SingleItem::Track(Track {id: 2}))));
-        assert!(matches!(queue.get_items()[7], QueueItem::Single(SingleItem::Track(Track {id: 0}))));
+        queue.shuffled = true;
+        queue.unshuffle_strat = UnshuffleStrategy::KeepIndex;
+
+        queue.next().unwrap();
+        queue.next().unwrap();
+        queue.unshuffle();
+
+        assert_eq!(queue.shuffle_order, Some(vec![7, 3, 5, 6, 7]));
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 5})))));
+        queue.next().unwrap();
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 6})))));
+        queue.next().unwrap();
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 7})))));
+        assert!(matches!(queue.next(), Err(QueueError::ReachedEnd)));
     }
 
     #[test]
-    fn get_items_shuffled_playing_middle() {
+    fn unshuffle_strat_keep_index_end() {
         let mut queue: OldQueue<SingleItem, CollectionItem> = OldQueue::from(vec![
             QueueItem::Single(SingleItem::Track(Track {id: 0})),
             QueueItem::Single(SingleItem::Track(Track {id: 1})),
@@ -875,80 +2752,3348 @@ mod tests {
             QueueItem::Single(SingleItem::Track(Track {id: 7})),
         ]);
 
-        queue.next().unwrap();
-        queue.next().unwrap();
-        queue.next().unwrap(); // 3
+        queue.shuffle_order = Some(vec![7, 3, 5, 1, 2, 6, 4, 0]);
 
-        queue.shuffle_order = Some(vec![0, 1, 2, 3, 6, 4, 7, 5]);
+        queue.shuffled = true;
+        queue.unshuffle_strat = UnshuffleStrategy::KeepIndex;
 
-        assert!(matches!(queue.get_items()[0], QueueItem::Single(SingleItem::Track(Track {id: 0}))));
-        assert!(matches!(queue.get_items()[1], QueueItem::Single(SingleItem::Track(Track {id: 1}))));
-        assert!(matches!(queue.get_items()[2], QueueItem::Single(SingleItem::Track(Track {id: 2}))));
-        assert!(matches!(queue.get_items()[3], QueueItem::Single(SingleItem::Track(Track {id: 3}))));
-        assert!(matches!(queue.get_items()[4], QueueItem::Single(SingleItem::Track(Track {id: 6}))));
-        assert!(matches!(queue.get_items()[7], QueueItem::Single(SingleItem::Track(Track {id: 5}))));
+        for _ in 0..7 {
+            queue.next().unwrap();
+        }
+        queue.unshuffle();
+
+        assert_eq!(queue.shuffle_order, Some(vec![7, 3, 5, 1, 2, 6, 4, 0, 1, 2, 3, 4, 5, 6, 7]));
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 0})))));
+        queue.next().unwrap();
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 1})))));
+        queue.previous().unwrap();
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 0})))));
+        queue.previous().unwrap();
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 4})))));
     }
 
     #[test]
-    fn queue() {
+    fn unshuffle_strat_keep_index_history() {
         let mut queue: OldQueue<SingleItem, CollectionItem> = OldQueue::from(vec![
             QueueItem::Single(SingleItem::Track(Track {id: 0})),
             QueueItem::Single(SingleItem::Track(Track {id: 1})),
+            QueueItem::Single(SingleItem::Track(Track {id: 2})),
+            QueueItem::Single(SingleItem::Track(Track {id: 3})),
+            QueueItem::Single(SingleItem::Track(Track {id: 4})),
+            QueueItem::Single(SingleItem::Track(Track {id: 5})),
+            QueueItem::Single(SingleItem::Track(Track {id: 6})),
+            QueueItem::Single(SingleItem::Track(Track {id: 7})),
         ]);
 
-        queue.queue(QueueItem::Single(SingleItem::Track(Track {id: 2})));
+        queue.shuffle_order = Some(vec![7, 3, 5, 1, 2, 0, 4, 6]);
 
-        assert!(matches!(queue.get_items()[2], QueueItem::Single(SingleItem::Track(Track {id: 2}))));
+        queue.shuffled = true;
+        queue.unshuffle_strat = UnshuffleStrategy::KeepIndex;
+
+        queue.next().unwrap();
+        queue.next().unwrap();
+        queue.next().unwrap();
+        queue.previous().unwrap();
+        queue.previous().unwrap();
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 3})))));
+        queue.unshuffle();
+
+        assert_eq!(queue.shuffle_order, Some(vec![7, 3, 5, 1, 2, 3, 4, 5, 6, 7]));
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 3})))));
+        queue.next().unwrap();
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 5})))));
+        queue.next().unwrap();
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 1})))));
+        queue.next().unwrap();
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 2})))));
     }
 
-    #[test] 
-    fn get_current_item_single_items_simple() {
+    #[test]
+    fn unshuffle_strat_from_beginning_start() {
         let mut queue: OldQueue<SingleItem, CollectionItem> = OldQueue::from(vec![
             QueueItem::Single(SingleItem::Track(Track {id: 0})),
-            QueueItem::Single(SingleItem::Episode(Episode {id: 0})),
-            QueueItem::Single(SingleItem::Track(Track {id: 9})),
+            QueueItem::Single(SingleItem::Track(Track {id: 1})),
+            QueueItem::Single(SingleItem::Track(Track {id: 2})),
+            QueueItem::Single(SingleItem::Track(Track {id: 3})),
+            QueueItem::Single(SingleItem::Track(Track {id: 4})),
+            QueueItem::Single(SingleItem::Track(Track {id: 5})),
+            QueueItem::Single(SingleItem::Track(Track {id: 6})),
             QueueItem::Single(SingleItem::Track(Track {id: 7})),
-            QueueItem::Single(SingleItem::Episode(Episode {id: 3})),
         ]);
 
-        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 0})))));
-        queue.next().unwrap();
-        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Episode(Episode {id: 0})))));
+        queue.shuffle_order = Some(vec![7, 3, 5, 1, 2, 0, 4, 6]);
+
+        queue.shuffled = true;
+        queue.unshuffle_strat = UnshuffleStrategy::FromBeginning;
+
+        queue.unshuffle();
+
+        assert_eq!(queue.shuffle_order, Some(vec![7, 0, 1, 2, 3, 4, 5, 6, 7]));
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 7})))));
         queue.next().unwrap();
-        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 9})))));
-        queue.previous().unwrap();
-        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Episode(Episode {id: 0})))));
-        queue.previous().unwrap();
         assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 0})))));
-        assert!(matches!(queue.previous(), Err(QueueError::ReachedBeginning)));
         queue.next().unwrap();
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 1})))));
+    }
+
+    #[test]
+    fn unshuffle_strat_from_beginning_middle() {
+        let mut queue: OldQueue<SingleItem, CollectionItem> = OldQueue::from(vec![
+            QueueItem::Single(SingleItem::Track(Track {id: 0})),
+            QueueItem::Single(SingleItem::Track(Track {id: 1})),
+            QueueItem::Single(SingleItem::Track(Track {id: 2})),
+            QueueItem::Single(SingleItem::Track(Track {id: 3})),
+            QueueItem::Single(SingleItem::Track(Track {id: 4})),
+            QueueItem::Single(SingleItem::Track(Track {id: 5})),
+            QueueItem::Single(SingleItem::Track(Track {id: 6})),
+            QueueItem::Single(SingleItem::Track(Track {id: 7})),
+        ]);
+
+        queue.shuffle_order = Some(vec![7, 3, 5, 1, 2, 0, 4, 6]);
+
+        queue.shuffled = true;
+        queue.unshuffle_strat = UnshuffleStrategy::FromBeginning;
+
         queue.next().unwrap();
         queue.next().unwrap();
+        queue.unshuffle();
+
+        assert_eq!(queue.shuffle_order, Some(vec![7, 3, 5, 0, 1, 2, 3, 4, 5, 6, 7]));
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 5})))));
+        for _ in 0..8 {
+            queue.next().unwrap();
+        }
         assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 7})))));
-        queue.next().unwrap();
-        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Episode(Episode {id: 3})))));
         assert!(matches!(queue.next(), Err(QueueError::ReachedEnd)));
     }
 
     #[test]
-    fn queue_next_single_items_simple() {
+    fn unshuffle_strat_from_beginning_end() {
         let mut queue: OldQueue<SingleItem, CollectionItem> = OldQueue::from(vec![
             QueueItem::Single(SingleItem::Track(Track {id: 0})),
-            QueueItem::Single(SingleItem::Episode(Episode {id: 0})),
-            QueueItem::Single(SingleItem::Track(Track {id: 9})),
+            QueueItem::Single(SingleItem::Track(Track {id: 1})),
+            QueueItem::Single(SingleItem::Track(Track {id: 2})),
+            QueueItem::Single(SingleItem::Track(Track {id: 3})),
+            QueueItem::Single(SingleItem::Track(Track {id: 4})),
+            QueueItem::Single(SingleItem::Track(Track {id: 5})),
+            QueueItem::Single(SingleItem::Track(Track {id: 6})),
             QueueItem::Single(SingleItem::Track(Track {id: 7})),
-            QueueItem::Single(SingleItem::Episode(Episode {id: 3})),
         ]);
 
+        queue.shuffle_order = Some(vec![7, 3, 5, 1, 2, 0, 4, 6]);
+
+        queue.shuffled = true;
+        queue.unshuffle_strat = UnshuffleStrategy::FromBeginning;
+
+        for _ in 0..7 {
+            queue.next().unwrap();
+        }
+        queue.unshuffle();
+
+        assert_eq!(queue.shuffle_order.as_ref().map(|order| order.len()), Some(16));
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 6})))));
         queue.next().unwrap();
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 0})))));
+    }
+
+    #[test]
+    fn unshuffle_strat_from_beginning_history() {
+        let mut queue: OldQueue<SingleItem, CollectionItem> = OldQueue::from(vec![
+            QueueItem::Single(SingleItem::Track(Track {id: 0})),
+            QueueItem::Single(SingleItem::Track(Track {id: 1})),
+            QueueItem::Single(SingleItem::Track(Track {id: 2})),
+            QueueItem::Single(SingleItem::Track(Track {id: 3})),
+            QueueItem::Single(SingleItem::Track(Track {id: 4})),
+            QueueItem::Single(SingleItem::Track(Track {id: 5})),
+            QueueItem::Single(SingleItem::Track(Track {id: 6})),
+            QueueItem::Single(SingleItem::Track(Track {id: 7})),
+        ]);
+
+        queue.shuffle_order = Some(vec![7, 3, 5, 1, 2, 0, 4, 6]);
+
+        queue.shuffled = true;
+        queue.unshuffle_strat = UnshuffleStrategy::FromBeginning;
+
         queue.next().unwrap();
-        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 9})))));
-        queue.queue_next(QueueItem::Single(SingleItem::Track(Track {id: 3})));
         queue.next().unwrap();
+        queue.previous().unwrap();
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 3})))));
+        queue.unshuffle();
         assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 3})))));
         queue.next().unwrap();
-        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 7})))));
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 5})))));
+        queue.next().unwrap();
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 0})))));
         queue.previous().unwrap();
-        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 3})))));
+        queue.previous().unwrap();
+        queue.previous().unwrap();
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 7})))));
+        assert_eq!(queue.history, vec![(ItemIndex::Queue(7), 0), (ItemIndex::Queue(3), 0), (ItemIndex::Queue(5), 0)]);
+    }
+
+    #[test]
+    fn unshuffle_strategy_options() {
+        let mut queue: OldQueue<SingleItem, CollectionItem> = OldQueue::from(vec![
+            QueueItem::Single(SingleItem::Track(Track {id: 0})),
+            QueueItem::Single(SingleItem::Track(Track {id: 1})),
+            QueueItem::Single(SingleItem::Track(Track {id: 2})),
+        ])
+        .with_repeat_status(Some(RepeatMode::All))
+        .with_unshuffle_strategy(UnshuffleStrategy::FromBeginning);
+
+        assert_eq!(queue.repeat_status, Some(RepeatMode::All));
+        assert_eq!(queue.unshuffle_strategy(), UnshuffleStrategy::FromBeginning);
+
+        queue.shuffle_order = Some(vec![2, 0, 1]);
+
+        queue.shuffled = true;
+        queue.set_unshuffle_strategy(UnshuffleStrategy::KeepIndex);
+        assert_eq!(queue.unshuffle_strategy(), UnshuffleStrategy::KeepIndex);
+        queue.unshuffle();
+        assert_eq!(queue.shuffle_order, Some(vec![2]));
+    }
+
+    #[test]
+    fn unshuffle_strategy_default() {
+        let queue: OldQueue<SingleItem, CollectionItem> = OldQueue::default();
+
+        assert_eq!(queue.repeat_status, None);
+        assert_eq!(queue.unshuffle_strategy(), UnshuffleStrategy::PlayUnplayed);
+    }
+
+    #[test]
+    fn seeded_shuffle() {
+        let items = || (0..32).map(|id| QueueItem::Single(SingleItem::Track(Track {id}))).collect::<Vec<_>>();
+        let mut queue: OldQueue<SingleItem, CollectionItem> = OldQueue::from(items()).with_seed(42);
+        let mut other: OldQueue<SingleItem, CollectionItem> = OldQueue::from(items()).with_seed(42);
+
+        assert_eq!(queue.seed(), 42);
+        queue.shuffle();
+        other.shuffle();
+        assert_eq!(queue.shuffle_order, other.shuffle_order);
+
+        queue.next().unwrap();
+        other.next().unwrap();
+        queue.shuffle();
+        other.shuffle();
+        assert_eq!(queue.shuffle_order, other.shuffle_order);
+
+        let mut different: OldQueue<SingleItem, CollectionItem> = OldQueue::from(items()).with_seed(43);
+        different.shuffle();
+        assert_ne!(queue.shuffle_order, different.shuffle_order);
+    }
+
+    #[test]
+    fn seeded_shuffle_replay() {
+        let mut queue: OldQueue<SingleItem, CollectionItem> = OldQueue::from(
+            (0..32).map(|id| QueueItem::Single(SingleItem::Track(Track {id}))).collect::<Vec<_>>(),
+        );
+
+        queue.shuffle();
+        let shuffle_order = queue.shuffle_order.clone();
+        queue.set_seed(queue.seed());
+        queue.shuffle_order = None;
+        queue.shuffle();
+        assert_eq!(queue.shuffle_order, shuffle_order);
+    }
+
+    #[test]
+    fn get_items_single_items_simple() {
+        let queue: OldQueue<SingleItem, CollectionItem> = OldQueue::from(vec![
+            QueueItem::Single(SingleItem::Track(Track {id: 0})),
+            QueueItem::Single(SingleItem::Track(Track {id: 1})),
+            QueueItem::Single(SingleItem::Track(Track {id: 2})),
+            QueueItem::Single(SingleItem::Track(Track {id: 3})),
+        ]);
+
+
+        assert!(matches!(queue.get_items()[0], QueueItem::Single(SingleItem::Track(Track {id: 0}))));
+        assert!(matches!(queue.get_items()[1], QueueItem::Single(SingleItem::Track(Track {id: 1}))));
+        assert!(matches!(queue.get_items()[2], QueueItem::Single(SingleItem::Track(Track {id: 2}))));
+        assert!(matches!(queue.get_items()[3], QueueItem::Single(SingleItem::Track(Track {id: 3}))));
+    }
+
+    #[test]
+    fn get_items_shuffled_playing_start() {
+        let mut queue: OldQueue<SingleItem, CollectionItem> = OldQueue::from(vec![
+            QueueItem::Single(SingleItem::Track(Track {id: 0})),
+            QueueItem::Single(SingleItem::Track(Track {id: 1})),
+            QueueItem::Single(SingleItem::Track(Track {id: 2})),
+            QueueItem::Single(SingleItem::Track(Track {id: 3})),
+            QueueItem::Single(SingleItem::Track(Track {id: 4})),
+            QueueItem::Single(SingleItem::Track(Track {id: 5})),
+            QueueItem::Single(SingleItem::Track(Track {id: 6})),
+            QueueItem::Single(SingleItem::Track(Track {id: 7})),
+        ]);
+
+        queue.shuffle_order = Some(vec![3, 1, 7, 2, 6, 4, 5, 0]);
+
+        queue.shuffled = true;
+
+        assert!(matches!(queue.get_items()[0], QueueItem::Single(SingleItem::Track(Track {id: 3}))));
+        assert!(matches!(queue.get_items()[1], QueueItem::Single(SingleItem::Track(Track {id: 1}))));
+        assert!(matches!(queue.get_items()[2], QueueItem::Single(SingleItem::Track(Track {id: 7}))));
+        assert!(matches!(queue.get_items()[3], QueueItem::Single(SingleItem::Track(Track {id: 2}))));
+        assert!(matches!(queue.get_items()[7], QueueItem::Single(SingleItem::Track(Track {id: 0}))));
+    }
+
+    #[test]
+    fn get_items_shuffled_playing_middle() {
+        let mut queue: OldQueue<SingleItem, CollectionItem> = OldQueue::from(vec![
+            QueueItem::Single(SingleItem::Track(Track {id: 0})),
+            QueueItem::Single(SingleItem::Track(Track {id: 1})),
+            QueueItem::Single(SingleItem::Track(Track {id: 2})),
+            QueueItem::Single(SingleItem::Track(Track {id: 3})),
+            QueueItem::Single(SingleItem::Track(Track {id: 4})),
+            QueueItem::Single(SingleItem::Track(Track {id: 5})),
+            QueueItem::Single(SingleItem::Track(Track {id: 6})),
+            QueueItem::Single(SingleItem::Track(Track {id: 7})),
+        ]);
+
+        queue.next().unwrap();
+        queue.next().unwrap();
+        queue.next().unwrap(); // 3
+
+        queue.shuffle_order = Some(vec![0, 1, 2, 3, 6, 4, 7, 5]);
+
+        queue.shuffled = true;
+
+        assert!(matches!(queue.get_items()[0], QueueItem::Single(SingleItem::Track(Track {id: 0}))));
+        assert!(matches!(queue.get_items()[1], QueueItem::Single(SingleItem::Track(Track {id: 1}))));
+        assert!(matches!(queue.get_items()[2], QueueItem::Single(SingleItem::Track(Track {id: 2}))));
+        assert!(matches!(queue.get_items()[3], QueueItem::Single(SingleItem::Track(Track {id: 3}))));
+        assert!(matches!(queue.get_items()[4], QueueItem::Single(SingleItem::Track(Track {id: 6}))));
+        assert!(matches!(queue.get_items()[7], QueueItem::Single(SingleItem::Track(Track {id: 5}))));
+    }
+
+    fn track_ids(items: &[&QueueItem<SingleItem, CollectionItem>]) -> Vec<u32> {
+        items.iter().map(|item| match item {
+            QueueItem::Single(SingleItem::Track(Track {id})) => *id,
+            _ => unreachable!(),
+        }).collect()
+    }
+
+    #[test]
+    fn get_items_stopped() {
+        let mut queue: OldQueue<SingleItem, CollectionItem> = OldQueue::default();
+        queue.queue(QueueItem::Single(SingleItem::Track(Track {id: 0})));
+        queue.queue(QueueItem::Single(SingleItem::Track(Track {id: 1})));
+        queue.queue_next(QueueItem::Single(SingleItem::Track(Track {id: 10})));
+
+        let items = queue.get_items();
+        assert!(items.history.is_empty());
+        assert!(items.current.is_none());
+        assert_eq!(track_ids(&items.up_next), vec![10]);
+        assert_eq!(track_ids(&items.remaining), vec![0, 1]);
+        assert_eq!(items.len(), 3);
+        assert!(matches!(items[0], QueueItem::Single(SingleItem::Track(Track {id: 10}))));
+    }
+
+    #[test]
+    fn get_items_stopped_shuffled() {
+        let mut queue: OldQueue<SingleItem, CollectionItem> = OldQueue::default();
+        queue.shuffle();
+        for id in 0..8 {
+            queue.queue(QueueItem::Single(SingleItem::Track(Track {id})));
+        }
+
+        let mut ids = track_ids(&queue.get_items().remaining);
+        assert_eq!(ids.len(), 8);
+        ids.sort();
+        assert_eq!(ids, (0..8).collect::<Vec<u32>>());
+
+        queue.shuffle_order = Some(vec![3, 1, 7, 2, 6, 4, 5, 0]);
+
+        queue.shuffled = true;
+        let items = queue.get_items();
+        assert!(items.current.is_none());
+        assert_eq!(track_ids(&items.remaining), vec![3, 1, 7, 2, 6, 4, 5, 0]);
+    }
+
+    #[test]
+    fn get_items_playing() {
+        let mut queue: OldQueue<SingleItem, CollectionItem> = OldQueue::from(vec![
+            QueueItem::Single(SingleItem::Track(Track {id: 0})),
+            QueueItem::Single(SingleItem::Track(Track {id: 1})),
+            QueueItem::Single(SingleItem::Track(Track {id: 2})),
+            QueueItem::Single(SingleItem::Track(Track {id: 3})),
+        ]);
+        queue.next().unwrap();
+        queue.queue_next(QueueItem::Single(SingleItem::Track(Track {id: 10})));
+        queue.queue_next(QueueItem::Single(SingleItem::Track(Track {id: 11})));
+
+        let items = queue.get_items();
+        assert_eq!(track_ids(&items.history), vec![0]);
+        assert!(matches!(items.current, Some(QueueItem::Single(SingleItem::Track(Track {id: 1})))));
+        assert_eq!(track_ids(&items.up_next), vec![10, 11]);
+        assert_eq!(track_ids(&items.remaining), vec![2, 3]);
+        assert_eq!(track_ids(&items.iter().collect::<Vec<_>>()), vec![0, 1, 10, 11, 2, 3]);
+
+        queue.next().unwrap();
+        let items = queue.get_items();
+        assert_eq!(track_ids(&items.history), vec![0, 1]);
+        assert!(matches!(items.current, Some(QueueItem::Single(SingleItem::Track(Track {id: 10})))));
+        assert_eq!(track_ids(&items.up_next), vec![11]);
+        assert_eq!(track_ids(&items.remaining), vec![2, 3]);
+    }
+
+    #[test]
+    fn get_items_playing_shuffled_history() {
+        let mut queue: OldQueue<SingleItem, CollectionItem> = OldQueue::from(vec![
+            QueueItem::Single(SingleItem::Track(Track {id: 0})),
+            QueueItem::Single(SingleItem::Track(Track {id: 1})),
+            QueueItem::Single(SingleItem::Track(Track {id: 2})),
+            QueueItem::Single(SingleItem::Track(Track {id: 3})),
+        ]);
+        queue.shuffle_order = Some(vec![2, 0, 3, 1]);
+        queue.shuffled = true;
+        queue.next().unwrap();
+        queue.next().unwrap();
+        queue.previous().unwrap();
+
+        let items = queue.get_items();
+        assert_eq!(track_ids(&items.history), vec![2, 0]);
+        assert_eq!(items.history_index, Some(1));
+        assert!(matches!(items.current, Some(QueueItem::Single(SingleItem::Track(Track {id: 3})))));
+        assert!(items.up_next.is_empty());
+        assert_eq!(track_ids(&items.remaining), vec![1]);
+    }
+
+    #[test]
+    fn get_items_after_clear() {
+        let mut queue: OldQueue<SingleItem, CollectionItem> = OldQueue::from(vec![
+            QueueItem::Single(SingleItem::Track(Track {id: 0})),
+            QueueItem::Single(SingleItem::Track(Track {id: 1})),
+        ]);
+        queue.shuffle_order = Some(vec![1, 0]);
+        queue.shuffled = true;
+        queue.next().unwrap();
+        queue.clear();
+        queue.queue(QueueItem::Single(SingleItem::Track(Track {id: 5})));
+
+        let items = queue.get_items();
+        assert!(items.history.is_empty());
+        assert!(items.current.is_none());
+        assert_eq!(track_ids(&items.remaining), vec![5]);
+        assert!(queue.is_shuffled());
+    }
+
+    #[test]
+    fn queue() {
+        let mut queue: OldQueue<SingleItem, CollectionItem> = OldQueue::from(vec![
+            QueueItem::Single(SingleItem::Track(Track {id: 0})),
+            QueueItem::Single(SingleItem::Track(Track {id: 1})),
+        ]);
+
+        queue.queue(QueueItem::Single(SingleItem::Track(Track {id: 2})));
+
+        assert!(matches!(queue.get_items()[2], QueueItem::Single(SingleItem::Track(Track {id: 2}))));
+    }
+
+    #[test] 
+    fn get_current_item_single_items_simple() {
+        let mut queue: OldQueue<SingleItem, CollectionItem> = OldQueue::from(vec![
+            QueueItem::Single(SingleItem::Track(Track {id: 0})),
+            QueueItem::Single(SingleItem::Episode(Episode {id: 0})),
+            QueueItem::Single(SingleItem::Track(Track {id: 9})),
+            QueueItem::Single(SingleItem::Track(Track {id: 7})),
+            QueueItem::Single(SingleItem::Episode(Episode {id: 3})),
+        ]);
+
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 0})))));
+        queue.next().unwrap();
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Episode(Episode {id: 0})))));
+        queue.next().unwrap();
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 9})))));
+        queue.previous().unwrap();
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Episode(Episode {id: 0})))));
+        queue.previous().unwrap();
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 0})))));
+        assert!(matches!(queue.previous(), Err(QueueError::ReachedBeginning)));
+        queue.next().unwrap();
+        queue.next().unwrap();
+        queue.next().unwrap();
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 7})))));
+        queue.next().unwrap();
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Episode(Episode {id: 3})))));
+        assert!(matches!(queue.next(), Err(QueueError::ReachedEnd)));
+    }
+
+    #[test]
+    fn queue_next_single_items_simple() {
+        let mut queue: OldQueue<SingleItem, CollectionItem> = OldQueue::from(vec![
+            QueueItem::Single(SingleItem::Track(Track {id: 0})),
+            QueueItem::Single(SingleItem::Episode(Episode {id: 0})),
+            QueueItem::Single(SingleItem::Track(Track {id: 9})),
+            QueueItem::Single(SingleItem::Track(Track {id: 7})),
+            QueueItem::Single(SingleItem::Episode(Episode {id: 3})),
+        ]);
+
+        queue.next().unwrap();
+        queue.next().unwrap();
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 9})))));
+        queue.queue_next(QueueItem::Single(SingleItem::Track(Track {id: 3})));
+        queue.next().unwrap();
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 3})))));
+        queue.next().unwrap();
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 7})))));
+        queue.previous().unwrap();
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 3})))));
+        queue.previous().unwrap();
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 9})))));
+        queue.next().unwrap();
+        queue.next().unwrap();
+        queue.next().unwrap();
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Episode(Episode {id: 3})))));
+        assert!(matches!(queue.next(), Err(QueueError::ReachedEnd)));
+        assert_eq!(queue.history, vec![
+            (ItemIndex::Queue(0), 0),
+            (ItemIndex::Queue(1), 0),
+            (ItemIndex::Queue(2), 0),
+            (ItemIndex::NextUp(0), 0),
+            (ItemIndex::Queue(3), 0),
+        ]);
+    }
+
+    #[test]
+    fn queue_next_multiple_items() {
+        let mut queue: OldQueue<SingleItem, CollectionItem> = OldQueue::from(vec![
+            QueueItem::Single(SingleItem::Track(Track {id: 0})),
+            QueueItem::Single(SingleItem::Track(Track {id: 1})),
+            QueueItem::Single(SingleItem::Track(Track {id: 2})),
+        ]);
+
+        queue.queue_next(QueueItem::Single(SingleItem::Track(Track {id: 10})));
+        queue.queue_next(QueueItem::Single(SingleItem::Track(Track {id: 11})));
+
+        assert_eq!(track_ids(&queue.get_items().iter().collect::<Vec<_>>()), vec![0, 10, 11, 1, 2]);
+
+        queue.next().unwrap();
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 10})))));
+        queue.queue_next(QueueItem::Single(SingleItem::Track(Track {id: 12})));
+        queue.next().unwrap();
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 11})))));
+
+        assert_eq!(track_ids(&queue.get_items().iter().collect::<Vec<_>>()), vec![0, 10, 11, 12, 1, 2]);
+
+        queue.next().unwrap();
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 12})))));
+        queue.next().unwrap();
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 1})))));
+        queue.queue_next(QueueItem::Single(SingleItem::Track(Track {id: 13})));
+        queue.next().unwrap();
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 13})))));
+        queue.next().unwrap();
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 2})))));
+        assert!(queue.next().is_err());
+    }
+
+    #[test]
+    fn queue_next_at_end() {
+        let mut queue: OldQueue<SingleItem, CollectionItem> = OldQueue::from(vec![
+            QueueItem::Single(SingleItem::Track(Track {id: 0})),
+        ]);
+
+        assert!(matches!(queue.next(), Err(QueueError::ReachedEnd)));
+        queue.queue_next(QueueItem::Single(SingleItem::Track(Track {id: 10})));
+        queue.next().unwrap();
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 10})))));
+        assert!(matches!(queue.next(), Err(QueueError::ReachedEnd)));
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 10})))));
+
+        queue.queue(QueueItem::Single(SingleItem::Track(Track {id: 1})));
+        queue.next().unwrap();
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 1})))));
+    }
+
+    #[test]
+    fn queue_next_shuffled() {
+        let mut queue: OldQueue<SingleItem, CollectionItem> = OldQueue::from(vec![
+            QueueItem::Single(SingleItem::Track(Track {id: 0})),
+            QueueItem::Single(SingleItem::Track(Track {id: 1})),
+            QueueItem::Single(SingleItem::Track(Track {id: 2})),
+            QueueItem::Single(SingleItem::Track(Track {id: 3})),
+        ]);
+        queue.shuffle_order = Some(vec![3, 1, 0, 2]);
+        queue.shuffled = true;
+
+        queue.queue_next(QueueItem::Single(SingleItem::Track(Track {id: 10})));
+        queue.next().unwrap();
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 10})))));
+
+        // The next item of the queue didn't play yet, so it can move.
+        queue.unshuffle();
+        assert_eq!(queue.shuffle_order, Some(vec![3, 0, 1, 2]));
+        queue.next().unwrap();
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 0})))));
+    }
+
+    #[test]
+    fn queue_next_collection() {
+        let mut queue: OldQueue<SingleItem, SimpleCollection<SingleItem>> = OldQueue::from(vec![
+            QueueItem::Single(SingleItem::Track(Track {id: 0})),
+            QueueItem::Single(SingleItem::Track(Track {id: 1})),
+        ]);
+
+        queue.queue_next(album(10..12));
+        queue.next().unwrap();
+        assert!(matches!(queue.current_track(), Ok(SingleItem::Track(Track {id: 10}))));
+        queue.next().unwrap();
+        assert!(matches!(queue.current_track(), Ok(SingleItem::Track(Track {id: 11}))));
+        queue.next().unwrap();
+        assert!(matches!(queue.current_track(), Ok(SingleItem::Track(Track {id: 1}))));
+        queue.previous().unwrap();
+        assert!(matches!(queue.current_track(), Ok(SingleItem::Track(Track {id: 11}))));
+    }
+
+    #[test]
+    fn queue_next_play() {
+        let mut queue: OldQueue<SingleItem, CollectionItem> = OldQueue::default();
+
+        queue.queue(QueueItem::Single(SingleItem::Track(Track {id: 0})));
+        queue.queue_next(QueueItem::Single(SingleItem::Track(Track {id: 10})));
+        assert!(matches!(queue.get_current_item(), Err(QueueError::NotPlaying)));
+        queue.play().unwrap();
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 10})))));
+        queue.next().unwrap();
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 0})))));
+
+        queue.clear();
+        assert!(matches!(queue.get_current_item(), Err(QueueError::NotPlaying)));
+        assert!(matches!(queue.previous(), Err(QueueError::NotPlaying)));
+    }
+
+    #[test]
+    fn repeat_all_single_items() {
+        let mut queue: OldQueue<SingleItem, CollectionItem> = OldQueue::from(vec![
+            QueueItem::Single(SingleItem::Track(Track {id: 0})),
+            QueueItem::Single(SingleItem::Track(Track {id: 1})),
+            QueueItem::Single(SingleItem::Track(Track {id: 2})),
+        ]);
+        queue.set_repeat_status(Some(RepeatMode::All));
+
+        queue.next().unwrap();
+        queue.next().unwrap();
+        queue.next().unwrap();
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 0})))));
+        queue.next().unwrap();
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 1})))));
+        assert_eq!(queue.history, vec![(ItemIndex::Queue(0), 0), (ItemIndex::Queue(1), 0), (ItemIndex::Queue(2), 0), (ItemIndex::Queue(0), 0)]);
+
+        queue.previous().unwrap();
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 0})))));
+        queue.previous().unwrap();
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 2})))));
+        queue.next().unwrap();
+        queue.next().unwrap();
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 1})))));
+    }
+
+    #[test]
+    fn repeat_all_shuffled() {
+        let mut queue: OldQueue<SingleItem, CollectionItem> = OldQueue::from(vec![
+            QueueItem::Single(SingleItem::Track(Track {id: 0})),
+            QueueItem::Single(SingleItem::Track(Track {id: 1})),
+            QueueItem::Single(SingleItem::Track(Track {id: 2})),
+        ]);
+        queue.shuffle_order = Some(vec![2, 0, 1]);
+        queue.shuffled = true;
+        queue.set_repeat_status(Some(RepeatMode::All));
+
+        queue.next().unwrap();
+        queue.next().unwrap();
+        queue.next().unwrap();
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 2})))));
+        assert_eq!(queue.shuffle_order, Some(vec![2, 0, 1]));
+        assert_eq!(queue.history, vec![(ItemIndex::Queue(2), 0), (ItemIndex::Queue(0), 0), (ItemIndex::Queue(1), 0)]);
+    }
+
+    #[test]
+    fn repeat_all_reshuffle() {
+        let mut queue: OldQueue<SingleItem, CollectionItem> = OldQueue::from(vec![
+            QueueItem::Single(SingleItem::Track(Track {id: 0})),
+            QueueItem::Single(SingleItem::Track(Track {id: 1})),
+            QueueItem::Single(SingleItem::Track(Track {id: 2})),
+            QueueItem::Single(SingleItem::Track(Track {id: 3})),
+        ]);
+        queue.shuffle_order = Some(vec![2, 0, 3, 1]);
+        queue.shuffled = true;
+        queue.set_repeat_status(Some(RepeatMode::All));
+        queue.set_reshuffle_on_repeat(true);
+
+        for _ in 0..4 {
+            queue.next().unwrap();
+        }
+
+        let mut order = queue.shuffle_order.clone().unwrap();
+        order.sort();
+        assert_eq!(order, vec![0, 1, 2, 3]);
+        assert_eq!(queue.history, vec![(ItemIndex::Queue(2), 0), (ItemIndex::Queue(0), 0), (ItemIndex::Queue(3), 0), (ItemIndex::Queue(1), 0)]);
+        assert_eq!(queue.current_item, Some(0));
+    }
+
+    #[test]
+    fn shuffle_at_last_item() {
+        let mut queue: OldQueue<SingleItem, CollectionItem> = OldQueue::from(
+            (0..3).map(|id| QueueItem::Single(SingleItem::Track(Track {id}))).collect::<Vec<_>>(),
+        );
+        queue.next().unwrap();
+        queue.next().unwrap(); // 2
+        queue.shuffle();
+        assert_eq!(queue.shuffle_order, Some(vec![0, 1, 2]));
+
+        // Nothing was left to shuffle, but unshuffling still follows the strategy
+        queue.set_unshuffle_strategy(UnshuffleStrategy::FromBeginning);
+        queue.unshuffle();
+        assert_eq!(queue.shuffle_order, Some(vec![0, 1, 2, 0, 1, 2]));
+    }
+
+    #[test]
+    fn repeat_all_after_unshuffle_strategy() {
+        let mut queue: OldQueue<SingleItem, CollectionItem> = OldQueue::from(
+            (0..4).map(|id| QueueItem::Single(SingleItem::Track(Track {id}))).collect::<Vec<_>>(),
+        );
+        queue.shuffle_order = Some(vec![2, 0, 3, 1]);
+        queue.shuffled = true;
+        queue.next().unwrap(); // 0
+        queue.set_unshuffle_strategy(UnshuffleStrategy::FromBeginning);
+        queue.unshuffle();
+        queue.shuffle();
+        assert_eq!(queue.playback_len(), 6);
+        queue.set_repeat_status(Some(RepeatMode::All));
+
+        // The new pass plays every item once
+        for _ in 0..5 {
+            queue.next().unwrap();
+        }
+        let mut order = queue.shuffle_order.clone().unwrap();
+        order.sort();
+        assert_eq!(order, vec![0, 1, 2, 3]);
+        assert_eq!(queue.current_item, Some(0));
+    }
+
+    #[test]
+    fn repeat_all_previous_at_beginning() {
+        let mut queue: OldQueue<SingleItem, CollectionItem> = OldQueue::from(vec![
+            QueueItem::Single(SingleItem::Track(Track {id: 0})),
+            QueueItem::Single(SingleItem::Track(Track {id: 1})),
+            QueueItem::Single(SingleItem::Track(Track {id: 2})),
+        ]);
+        queue.set_repeat_status(Some(RepeatMode::All));
+
+        queue.previous().unwrap();
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 2})))));
+        queue.next().unwrap();
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 0})))));
+        queue.previous().unwrap();
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 2})))));
+        assert!(matches!(queue.previous(), Err(QueueError::ReachedBeginning)));
+    }
+
+    #[test]
+    fn repeat_item_single_items() {
+        let mut queue: OldQueue<SingleItem, CollectionItem> = OldQueue::from(vec![
+            QueueItem::Single(SingleItem::Track(Track {id: 0})),
+            QueueItem::Single(SingleItem::Track(Track {id: 1})),
+        ]);
+
+        queue.next().unwrap();
+        queue.set_repeat_status(Some(RepeatMode::Item));
+        queue.next().unwrap();
+        queue.next().unwrap();
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 1})))));
+        assert_eq!(queue.history, vec![(ItemIndex::Queue(0), 0), (ItemIndex::Queue(1), 0), (ItemIndex::Queue(1), 0)]);
+
+        queue.previous().unwrap();
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 1})))));
+        queue.previous().unwrap();
+        queue.previous().unwrap();
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 0})))));
+        queue.previous().unwrap();
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 0})))));
+        queue.next().unwrap();
+        queue.next().unwrap();
+        queue.next().unwrap();
+        assert_eq!(queue.history_index, None);
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 1})))));
+    }
+
+    #[test]
+    fn repeat_container_single_items() {
+        let mut queue: OldQueue<SingleItem, CollectionItem> = OldQueue::from(vec![
+            QueueItem::Single(SingleItem::Track(Track {id: 0})),
+            QueueItem::Single(SingleItem::Track(Track {id: 1})),
+        ]);
+        queue.set_repeat_status(Some(RepeatMode::Container));
+
+        queue.next().unwrap();
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 0})))));
+        queue.previous().unwrap();
+        queue.previous().unwrap();
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 0})))));
+    }
+
+    fn album(ids: std::ops::Range<u32>) -> QueueItem<SingleItem, SimpleCollection<SingleItem>> {
+        QueueItem::Collection(SimpleCollection::from(
+            ids.map(|id| SingleItem::Track(Track {id})).collect::<Vec<_>>(),
+        ))
+    }
+
+    #[test]
+    fn collection_items_simple() {
+        let mut queue: OldQueue<SingleItem, SimpleCollection<SingleItem>> = OldQueue::from(vec![
+            QueueItem::Single(SingleItem::Track(Track {id: 0})),
+            album(10..13),
+            QueueItem::Single(SingleItem::Episode(Episode {id: 1})),
+        ]);
+
+        assert!(matches!(queue.current_track(), Ok(SingleItem::Track(Track {id: 0}))));
+        queue.next().unwrap();
+        assert!(matches!(queue.current_track(), Ok(SingleItem::Track(Track {id: 10}))));
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Collection(_))));
+        queue.next().unwrap();
+        assert!(matches!(queue.current_track(), Ok(SingleItem::Track(Track {id: 11}))));
+        queue.next().unwrap();
+        assert!(matches!(queue.current_track(), Ok(SingleItem::Track(Track {id: 12}))));
+        queue.next().unwrap();
+        assert!(matches!(queue.current_track(), Ok(SingleItem::Episode(Episode {id: 1}))));
+        assert!(matches!(queue.next(), Err(QueueError::ReachedEnd)));
+        assert_eq!(queue.history, vec![(ItemIndex::Queue(0), 0), (ItemIndex::Queue(1), 0), (ItemIndex::Queue(1), 1), (ItemIndex::Queue(1), 2)]);
+
+        queue.previous().unwrap();
+        assert!(matches!(queue.current_track(), Ok(SingleItem::Track(Track {id: 12}))));
+        queue.previous().unwrap();
+        queue.previous().unwrap();
+        assert!(matches!(queue.current_track(), Ok(SingleItem::Track(Track {id: 10}))));
+        queue.previous().unwrap();
+        assert!(matches!(queue.current_track(), Ok(SingleItem::Track(Track {id: 0}))));
+        assert!(matches!(queue.previous(), Err(QueueError::ReachedBeginning)));
+        queue.next().unwrap();
+        queue.next().unwrap();
+        assert!(matches!(queue.current_track(), Ok(SingleItem::Track(Track {id: 11}))));
+    }
+
+    #[test]
+    fn collection_items_shuffled() {
+        let mut queue: OldQueue<SingleItem, SimpleCollection<SingleItem>> = OldQueue::from(vec![
+            album(0..2),
+            album(10..12),
+            QueueItem::Single(SingleItem::Track(Track {id: 20})),
+        ]);
+        queue.shuffle_order = Some(vec![1, 2, 0]);
+        queue.shuffled = true;
+
+        assert!(matches!(queue.current_track(), Ok(SingleItem::Track(Track {id: 10}))));
+        queue.next().unwrap();
+        assert!(matches!(queue.current_track(), Ok(SingleItem::Track(Track {id: 11}))));
+        queue.next().unwrap();
+        assert!(matches!(queue.current_track(), Ok(SingleItem::Track(Track {id: 20}))));
+        queue.next().unwrap();
+        assert!(matches!(queue.current_track(), Ok(SingleItem::Track(Track {id: 0}))));
+        queue.next().unwrap();
+        assert!(matches!(queue.current_track(), Ok(SingleItem::Track(Track {id: 1}))));
+        assert!(queue.next().is_err());
+    }
+
+    #[test]
+    fn collection_items_repeat_container() {
+        let mut queue: OldQueue<SingleItem, SimpleCollection<SingleItem>> = OldQueue::from(vec![
+            album(0..2),
+            QueueItem::Single(SingleItem::Track(Track {id: 20})),
+        ]);
+        queue.set_repeat_status(Some(RepeatMode::Container));
+
+        queue.next().unwrap();
+        assert!(matches!(queue.current_track(), Ok(SingleItem::Track(Track {id: 1}))));
+        queue.next().unwrap();
+        assert!(matches!(queue.current_track(), Ok(SingleItem::Track(Track {id: 0}))));
+        queue.next().unwrap();
+        assert!(matches!(queue.current_track(), Ok(SingleItem::Track(Track {id: 1}))));
+        assert_eq!(queue.history, vec![(ItemIndex::Queue(0), 0), (ItemIndex::Queue(0), 1), (ItemIndex::Queue(0), 0)]);
+
+        queue.set_repeat_status(None);
+        queue.next().unwrap();
+        assert!(matches!(queue.current_track(), Ok(SingleItem::Track(Track {id: 20}))));
+    }
+
+    #[test]
+    fn collection_items_repeat_item() {
+        let mut queue: OldQueue<SingleItem, SimpleCollection<SingleItem>> = OldQueue::from(vec![
+            album(0..3),
+        ]);
+
+        queue.next().unwrap();
+        queue.set_repeat_status(Some(RepeatMode::Item));
+        queue.next().unwrap();
+        assert!(matches!(queue.current_track(), Ok(SingleItem::Track(Track {id: 1}))));
+        assert_eq!(queue.history, vec![(ItemIndex::Queue(0), 0), (ItemIndex::Queue(0), 1)]);
+    }
+
+    #[test]
+    fn collection_items_repeat_all() {
+        let mut queue: OldQueue<SingleItem, SimpleCollection<SingleItem>> = OldQueue::from(vec![
+            QueueItem::Single(SingleItem::Track(Track {id: 20})),
+            album(0..2),
+        ]);
+        queue.set_repeat_status(Some(RepeatMode::All));
+
+        queue.previous().unwrap();
+        assert!(matches!(queue.current_track(), Ok(SingleItem::Track(Track {id: 1}))));
+        queue.next().unwrap();
+        assert!(matches!(queue.current_track(), Ok(SingleItem::Track(Track {id: 20}))));
+        queue.next().unwrap();
+        queue.next().unwrap();
+        queue.next().unwrap();
+        assert!(matches!(queue.current_track(), Ok(SingleItem::Track(Track {id: 20}))));
+    }
+
+    #[test]
+    fn collection_items_albums_and_playlists() {
+        let mut queue: OldQueue<SingleItem, CollectionItem> = OldQueue::from(vec![
+            QueueItem::Collection(CollectionItem::Album(Album {
+                tracks: SimpleCollection::from(vec![
+                    SingleItem::Track(Track {id: 0}),
+                    SingleItem::Track(Track {id: 1}),
+                ]),
+            })),
+            QueueItem::Collection(CollectionItem::Playlist(Playlist {
+                tracks: SimpleCollection::from(vec![]),
+            })),
+            QueueItem::Collection(CollectionItem::Playlist(Playlist {
+                tracks: SimpleCollection::from(vec![
+                    SingleItem::Episode(Episode {id: 2}),
+                ]),
+            })),
+        ]);
+
+        assert!(matches!(queue.current_track(), Ok(SingleItem::Track(Track {id: 0}))));
+        queue.next().unwrap();
+        assert!(matches!(queue.current_track(), Ok(SingleItem::Track(Track {id: 1}))));
+        queue.next().unwrap();
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Collection(CollectionItem::Playlist(_)))));
+        assert_eq!(queue.current_track().unwrap_err(), QueueError::EmptyCollection);
+        queue.next().unwrap();
+        assert!(matches!(queue.current_track(), Ok(SingleItem::Episode(Episode {id: 2}))));
+    }
+
+    #[test]
+    fn remove_unshuffled() {
+        let mut queue: OldQueue<SingleItem, CollectionItem> = OldQueue::from(
+            (0..5).map(|id| QueueItem::Single(SingleItem::Track(Track {id}))).collect::<Vec<_>>(),
+        );
+        queue.next().unwrap();
+        queue.next().unwrap(); // 2
+
+        queue.remove(3).unwrap();
+        queue.remove(0).unwrap();
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 2})))));
+        assert_eq!(queue.history, vec![(ItemIndex::Queue(0), 0)]);
+        assert_eq!(track_ids(&queue.get_items().iter().collect::<Vec<_>>()), vec![1, 2, 4]);
+        assert!(matches!(queue.remove(3), Err(QueueError::IndexOutOfBounds)));
+
+        queue.previous().unwrap();
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 1})))));
+        queue.next().unwrap();
+        queue.next().unwrap();
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 4})))));
+        assert!(queue.next().is_err());
+    }
+
+    #[test]
+    fn remove_shuffled() {
+        let mut queue: OldQueue<SingleItem, CollectionItem> = OldQueue::from(
+            (0..5).map(|id| QueueItem::Single(SingleItem::Track(Track {id}))).collect::<Vec<_>>(),
+        );
+        queue.shuffle_order = Some(vec![3, 0, 4, 1, 2]);
+        queue.shuffled = true;
+        queue.next().unwrap();
+        queue.next().unwrap(); // 4
+
+        queue.remove(0).unwrap();
+        assert_eq!(queue.shuffle_order, Some(vec![0, 3, 1, 2]));
+        assert_eq!(queue.history, vec![(ItemIndex::Queue(0), 0)]);
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 4})))));
+        queue.remove(3).unwrap();
+        assert_eq!(queue.shuffle_order, Some(vec![0, 2, 1]));
+        assert_eq!(track_ids(&queue.get_items().iter().collect::<Vec<_>>()), vec![0, 4, 1]);
+        queue.next().unwrap();
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 1})))));
+    }
+
+    #[test]
+    fn remove_current() {
+        let mut queue: OldQueue<SingleItem, CollectionItem> = OldQueue::from(
+            (0..3).map(|id| QueueItem::Single(SingleItem::Track(Track {id}))).collect::<Vec<_>>(),
+        );
+        queue.next().unwrap(); // 1
+
+        queue.remove(1).unwrap();
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 2})))));
+        queue.remove(1).unwrap();
+        assert!(matches!(queue.get_current_item(), Err(QueueError::NotPlaying)));
+        assert_eq!(queue.history, vec![(ItemIndex::Queue(0), 0)]);
+    }
+
+    #[test]
+    fn remove_current_plays_next_up() {
+        let mut queue: OldQueue<SingleItem, CollectionItem> = OldQueue::from(
+            (0..2).map(|id| QueueItem::Single(SingleItem::Track(Track {id}))).collect::<Vec<_>>(),
+        );
+        queue.next().unwrap(); // 1
+        queue.queue_next(QueueItem::Single(SingleItem::Track(Track {id: 10})));
+
+        queue.remove(1).unwrap();
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 10})))));
+        assert!(queue.next().is_err());
+    }
+
+    #[test]
+    fn remove_current_before_next_up() {
+        let mut queue: OldQueue<SingleItem, CollectionItem> = OldQueue::from(
+            (0..3).map(|id| QueueItem::Single(SingleItem::Track(Track {id}))).collect::<Vec<_>>(),
+        );
+        queue.next().unwrap(); // 1
+        queue.queue_next(QueueItem::Single(SingleItem::Track(Track {id: 10})));
+
+        // The next up item still plays before the rest of the queue
+        queue.remove(1).unwrap();
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 10})))));
+        assert_eq!(track_ids(&queue.get_items().iter().collect::<Vec<_>>()), vec![0, 10, 2]);
+        queue.next().unwrap();
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 2})))));
+        assert!(queue.next().is_err());
+    }
+
+    #[test]
+    fn remove_history_browsing() {
+        let mut queue: OldQueue<SingleItem, CollectionItem> = OldQueue::from(
+            (0..4).map(|id| QueueItem::Single(SingleItem::Track(Track {id}))).collect::<Vec<_>>(),
+        );
+        queue.next().unwrap();
+        queue.next().unwrap();
+        queue.next().unwrap(); // 3
+        queue.previous().unwrap();
+        queue.previous().unwrap();
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 1})))));
+
+        queue.remove(1).unwrap();
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 2})))));
+        assert_eq!(queue.history, vec![(ItemIndex::Queue(0), 0), (ItemIndex::Queue(1), 0)]);
+        queue.next().unwrap();
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 3})))));
+        assert!(queue.next().is_err());
+    }
+
+    #[test]
+    fn remove_rest_of_playback_order() {
+        let mut queue: OldQueue<SingleItem, CollectionItem> = OldQueue::from(
+            (0..3).map(|id| QueueItem::Single(SingleItem::Track(Track {id}))).collect::<Vec<_>>(),
+        ).with_unshuffle_strategy(UnshuffleStrategy::KeepIndex);
+        queue.shuffle_order = Some(vec![1, 2, 0]);
+        queue.shuffled = true;
+        queue.next().unwrap(); // 2
+        queue.unshuffle();
+        assert_eq!(queue.shuffle_order, Some(vec![1, 2]));
+
+        // Track 0 is left out of the playback order, but it is still in the queue
+        queue.remove_range(0..2).unwrap();
+        assert!(matches!(queue.get_current_item(), Err(QueueError::NotPlaying)));
+        assert_eq!(queue.shuffle_order, None);
+        assert_eq!(track_ids(&queue.get_items().iter().collect::<Vec<_>>()), vec![0]);
+        queue.play().unwrap();
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 0})))));
+    }
+
+    #[test]
+    fn remove_range_unshuffled() {
+        let mut queue: OldQueue<SingleItem, CollectionItem> = OldQueue::from(
+            (0..6).map(|id| QueueItem::Single(SingleItem::Track(Track {id}))).collect::<Vec<_>>(),
+        );
+        queue.next().unwrap(); // 1
+
+        queue.remove_range(2..5).unwrap();
+        assert_eq!(track_ids(&queue.get_items().iter().collect::<Vec<_>>()), vec![0, 1, 5]);
+        assert!(matches!(queue.remove_range(2..4), Err(QueueError::IndexOutOfBounds)));
+        queue.next().unwrap();
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 5})))));
+    }
+
+    #[test]
+    fn remove_range_shuffled() {
+        let mut queue: OldQueue<SingleItem, CollectionItem> = OldQueue::from(
+            (0..6).map(|id| QueueItem::Single(SingleItem::Track(Track {id}))).collect::<Vec<_>>(),
+        );
+        queue.shuffle_order = Some(vec![5, 2, 0, 4, 1, 3]);
+        queue.shuffled = true;
+        queue.next().unwrap(); // 2
+
+        queue.remove_range(0..3).unwrap();
+        assert_eq!(queue.shuffle_order, Some(vec![2, 0, 1]));
+        assert!(queue.history.is_empty());
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 4})))));
+        assert_eq!(track_ids(&queue.get_items().iter().collect::<Vec<_>>()), vec![4, 1, 3]);
+    }
+
+    #[test]
+    fn insert_at_unshuffled() {
+        let mut queue: OldQueue<SingleItem, CollectionItem> = OldQueue::from(
+            (0..3).map(|id| QueueItem::Single(SingleItem::Track(Track {id}))).collect::<Vec<_>>(),
+        );
+        queue.next().unwrap(); // 1
+
+        queue.insert_at(2, QueueItem::Single(SingleItem::Track(Track {id: 10}))).unwrap();
+        queue.insert_at(0, QueueItem::Single(SingleItem::Track(Track {id: 11}))).unwrap();
+        assert!(matches!(queue.insert_at(6, QueueItem::Single(SingleItem::Track(Track {id: 12}))), Err(QueueError::IndexOutOfBounds)));
+        assert_eq!(track_ids(&queue.get_items().iter().collect::<Vec<_>>()), vec![0, 1, 10, 2]);
+        assert_eq!(queue.history, vec![(ItemIndex::Queue(1), 0)]);
+        assert_eq!(queue.current_item, Some(2));
+        queue.previous().unwrap();
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 0})))));
+        queue.next().unwrap();
+        queue.next().unwrap();
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 10})))));
+    }
+
+    #[test]
+    fn insert_at_shuffled() {
+        let mut queue: OldQueue<SingleItem, CollectionItem> = OldQueue::from(
+            (0..3).map(|id| QueueItem::Single(SingleItem::Track(Track {id}))).collect::<Vec<_>>(),
+        );
+        queue.shuffle_order = Some(vec![2, 0, 1]);
+        queue.shuffled = true;
+        queue.next().unwrap(); // 0
+
+        queue.insert_at(2, QueueItem::Single(SingleItem::Track(Track {id: 10}))).unwrap();
+        queue.insert_at(4, QueueItem::Single(SingleItem::Track(Track {id: 11}))).unwrap();
+        assert_eq!(queue.shuffle_order, Some(vec![2, 0, 3, 1, 4]));
+        queue.next().unwrap();
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 10})))));
+        queue.next().unwrap();
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 1})))));
+        queue.next().unwrap();
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 11})))));
+    }
+
+    /// Play the rest of the queue and return the ids of all the tracks that
+    /// played, including the history.
+    fn played_ids(queue: &mut OldQueue<SingleItem, CollectionItem>) -> Vec<u32> {
+        while queue.next().is_ok() {}
+        let view = queue.get_items();
+        track_ids(&view.history.iter().copied().chain(view.current).collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn move_item_unshuffled() {
+        let mut queue: OldQueue<SingleItem, CollectionItem> = OldQueue::from(
+            (0..5).map(|id| QueueItem::Single(SingleItem::Track(Track {id}))).collect::<Vec<_>>(),
+        );
+        queue.next().unwrap(); // 1
+
+        queue.move_item(4, 2).unwrap();
+        assert_eq!(track_ids(&queue.get_items().iter().collect::<Vec<_>>()), vec![0, 1, 4, 2, 3]);
+        // The current item stays where it is
+        queue.move_item(1, 3).unwrap();
+        queue.move_item(1, 0).unwrap();
+        assert_eq!(track_ids(&queue.get_items().iter().collect::<Vec<_>>()), vec![0, 1, 4, 2, 3]);
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 1})))));
+        assert_eq!(queue.current_item, Some(1));
+        queue.move_item(4, 2).unwrap();
+        assert_eq!(queue.history, vec![(ItemIndex::Queue(0), 0)]);
+        assert!(matches!(queue.move_item(0, 5), Err(QueueError::IndexOutOfBounds)));
+        assert!(!queue.is_shuffled());
+        assert_eq!(played_ids(&mut queue), vec![0, 1, 3, 4, 2]);
+
+        // A played item that moves after the current item plays again
+        let mut queue: OldQueue<SingleItem, CollectionItem> = OldQueue::from(
+            (0..3).map(|id| QueueItem::Single(SingleItem::Track(Track {id}))).collect::<Vec<_>>(),
+        );
+        queue.next().unwrap(); // 1
+        queue.move_item(0, 2).unwrap();
+        assert_eq!(queue.history, vec![(ItemIndex::Queue(2), 0)]);
+        assert_eq!(played_ids(&mut queue), vec![0, 1, 2, 0]);
+    }
+
+    #[test]
+    fn move_item_shuffled() {
+        let mut queue: OldQueue<SingleItem, CollectionItem> = OldQueue::from(
+            (0..5).map(|id| QueueItem::Single(SingleItem::Track(Track {id}))).collect::<Vec<_>>(),
+        );
+        queue.shuffle_order = Some(vec![3, 1, 4, 0, 2]);
+        queue.shuffled = true;
+        queue.next().unwrap(); // 1
+
+        queue.move_item(4, 2).unwrap();
+        assert_eq!(queue.shuffle_order, Some(vec![3, 1, 2, 4, 0]));
+        queue.move_item(1, 4).unwrap();
+        assert_eq!(queue.shuffle_order, Some(vec![3, 1, 2, 4, 0]));
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 1})))));
+        queue.move_item(1, 0).unwrap();
+        assert_eq!(queue.shuffle_order, Some(vec![3, 1, 2, 4, 0]));
+        assert_eq!(queue.current_item, Some(1));
+        assert_eq!(queue.history, vec![(ItemIndex::Queue(3), 0)]);
+        assert_eq!(played_ids(&mut queue), vec![3, 1, 2, 4, 0]);
+    }
+
+    #[test]
+    fn swap_unshuffled() {
+        let mut queue: OldQueue<SingleItem, CollectionItem> = OldQueue::from(
+            (0..5).map(|id| QueueItem::Single(SingleItem::Track(Track {id}))).collect::<Vec<_>>(),
+        );
+        queue.next().unwrap(); // 1
+
+        queue.swap(2, 3).unwrap();
+        assert_eq!(track_ids(&queue.get_items().iter().collect::<Vec<_>>()), vec![0, 1, 3, 2, 4]);
+        // The other item becomes the last played item
+        queue.swap(0, 1).unwrap();
+        assert_eq!(track_ids(&queue.get_items().iter().collect::<Vec<_>>()), vec![0, 1, 3, 2, 4]);
+        // The other item plays next
+        queue.swap(4, 1).unwrap();
+        assert_eq!(track_ids(&queue.get_items().iter().collect::<Vec<_>>()), vec![0, 1, 4, 3, 2]);
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 1})))));
+        assert_eq!(queue.history, vec![(ItemIndex::Queue(0), 0)]);
+        assert!(matches!(queue.swap(0, 5), Err(QueueError::IndexOutOfBounds)));
+        assert_eq!(played_ids(&mut queue), vec![0, 1, 4, 3, 2]);
+    }
+
+    #[test]
+    fn swap_shuffled() {
+        let mut queue: OldQueue<SingleItem, CollectionItem> = OldQueue::from(
+            (0..5).map(|id| QueueItem::Single(SingleItem::Track(Track {id}))).collect::<Vec<_>>(),
+        );
+        queue.shuffle_order = Some(vec![2, 3, 0, 1, 4]);
+        queue.shuffled = true;
+        queue.next().unwrap(); // 3
+
+        queue.swap(2, 3).unwrap();
+        assert_eq!(queue.shuffle_order, Some(vec![2, 3, 1, 0, 4]));
+        queue.swap(1, 4).unwrap();
+        assert_eq!(queue.shuffle_order, Some(vec![2, 3, 4, 1, 0]));
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 3})))));
+        assert_eq!(queue.current_item, Some(1));
+        queue.swap(0, 1).unwrap();
+        assert_eq!(queue.shuffle_order, Some(vec![2, 3, 4, 1, 0]));
+        assert_eq!(played_ids(&mut queue), vec![2, 3, 4, 1, 0]);
+    }
+
+    #[test]
+    fn jump_to_add_to_history() {
+        let mut queue: OldQueue<SingleItem, CollectionItem> = OldQueue::from(
+            (0..6).map(|id| QueueItem::Single(SingleItem::Track(Track {id}))).collect::<Vec<_>>(),
+        );
+        queue.next().unwrap(); // 1
+
+        assert!(matches!(queue.jump_to(4, JumpPolicy::AddToHistory), Ok(QueueItem::Single(SingleItem::Track(Track {id: 4})))));
+        assert_eq!(queue.history, vec![(ItemIndex::Queue(0), 0), (ItemIndex::Queue(1), 0), (ItemIndex::Queue(2), 0), (ItemIndex::Queue(3), 0)]);
+        queue.next().unwrap();
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 5})))));
+        assert!(queue.next().is_err());
+    }
+
+    #[test]
+    fn jump_to_keep_unplayed() {
+        let mut queue: OldQueue<SingleItem, CollectionItem> = OldQueue::from(
+            (0..6).map(|id| QueueItem::Single(SingleItem::Track(Track {id}))).collect::<Vec<_>>(),
+        );
+        queue.next().unwrap(); // 1
+
+        assert!(matches!(queue.jump_to(4, JumpPolicy::KeepUnplayed), Ok(QueueItem::Single(SingleItem::Track(Track {id: 4})))));
+        assert_eq!(queue.history, vec![(ItemIndex::Queue(0), 0), (ItemIndex::Queue(1), 0)]);
+        assert_eq!(track_ids(&queue.get_items().iter().collect::<Vec<_>>()), vec![0, 1, 4, 2, 3, 5]);
+        queue.next().unwrap();
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 2})))));
+    }
+
+    #[test]
+    fn jump_to_played_item() {
+        let mut queue: OldQueue<SingleItem, CollectionItem> = OldQueue::from(
+            (0..5).map(|id| QueueItem::Single(SingleItem::Track(Track {id}))).collect::<Vec<_>>(),
+        );
+        queue.next().unwrap();
+        queue.next().unwrap();
+        queue.next().unwrap(); // 3
+
+        assert!(matches!(queue.jump_to(1, JumpPolicy::AddToHistory), Ok(QueueItem::Single(SingleItem::Track(Track {id: 1})))));
+        assert_eq!(track_ids(&queue.get_items().history), vec![0, 1, 2, 3]);
+        queue.next().unwrap();
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 4})))));
+        assert!(queue.next().is_err());
+    }
+
+    #[test]
+    fn jump_to_from_history() {
+        let mut queue: OldQueue<SingleItem, CollectionItem> = OldQueue::from(
+            (0..4).map(|id| QueueItem::Single(SingleItem::Track(Track {id}))).collect::<Vec<_>>(),
+        );
+        queue.next().unwrap(); // 1
+        queue.previous().unwrap();
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 0})))));
+
+        queue.jump_to(2, JumpPolicy::AddToHistory).unwrap();
+        assert_eq!(queue.history_index, None);
+        assert_eq!(queue.history, vec![(ItemIndex::Queue(0), 0), (ItemIndex::Queue(1), 0)]);
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 2})))));
+    }
+
+    #[test]
+    fn jump_to_stopped() {
+        let mut queue: OldQueue<SingleItem, CollectionItem> = OldQueue::default();
+        for id in 0..4 {
+            queue.queue(QueueItem::Single(SingleItem::Track(Track {id})));
+        }
+        queue.queue_next(QueueItem::Single(SingleItem::Track(Track {id: 10})));
+
+        queue.jump_to(2, JumpPolicy::KeepUnplayed).unwrap();
+        assert!(queue.history.is_empty());
+        assert_eq!(track_ids(&queue.get_items().iter().collect::<Vec<_>>()), vec![2, 10, 0, 1, 3]);
+        queue.next().unwrap();
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 10})))));
+        queue.next().unwrap();
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 0})))));
+    }
+
+    #[test]
+    fn jump_to_collection_history() {
+        let mut queue: OldQueue<SingleItem, SimpleCollection<SingleItem>> = OldQueue::from(vec![
+            QueueItem::Single(SingleItem::Track(Track {id: 0})),
+            album(10..12),
+            QueueItem::Single(SingleItem::Track(Track {id: 1})),
+        ]);
+        queue.play().unwrap();
+
+        queue.jump_to(2, JumpPolicy::AddToHistory).unwrap();
+        assert_eq!(queue.history, vec![(ItemIndex::Queue(0), 0), (ItemIndex::Queue(1), 0), (ItemIndex::Queue(1), 1)]);
+    }
+
+    #[test]
+    fn jump_to_raw_shuffled() {
+        let mut queue: OldQueue<SingleItem, CollectionItem> = OldQueue::from(
+            (0..5).map(|id| QueueItem::Single(SingleItem::Track(Track {id}))).collect::<Vec<_>>(),
+        );
+        queue.shuffle_order = Some(vec![3, 1, 4, 0, 2]);
+        queue.shuffled = true;
+        queue.next().unwrap(); // 1
+
+        assert!(matches!(queue.jump_to_raw(0, JumpPolicy::AddToHistory), Ok(QueueItem::Single(SingleItem::Track(Track {id: 0})))));
+        assert_eq!(queue.history, vec![(ItemIndex::Queue(3), 0), (ItemIndex::Queue(1), 0), (ItemIndex::Queue(4), 0)]);
+        assert_eq!(queue.current_item, Some(3));
+        assert!(matches!(queue.jump_to_raw(4, JumpPolicy::KeepUnplayed), Ok(QueueItem::Single(SingleItem::Track(Track {id: 4})))));
+        assert_eq!(queue.shuffle_order, Some(vec![3, 1, 0, 4, 2]));
+        queue.next().unwrap();
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 2})))));
+    }
+
+    #[test]
+    fn jump_to_out_of_bounds() {
+        let mut queue: OldQueue<SingleItem, CollectionItem> = OldQueue::from(
+            (0..3).map(|id| QueueItem::Single(SingleItem::Track(Track {id}))).collect::<Vec<_>>(),
+        );
+        assert!(matches!(queue.jump_to(3, JumpPolicy::AddToHistory), Err(QueueError::IndexOutOfBounds)));
+        assert!(matches!(queue.jump_to_raw(3, JumpPolicy::KeepUnplayed), Err(QueueError::IndexOutOfBounds)));
+        assert!(queue.history.is_empty());
+    }
+
+    type UndoSnapshot = (Vec<u32>, Vec<u32>, Option<Vec<usize>>, Option<usize>, Option<usize>, Vec<(ItemIndex, usize)>);
+
+    fn undo_snapshot(queue: &OldQueue<SingleItem, CollectionItem>) -> UndoSnapshot {
+        (
+            track_ids(&queue.items.iter().collect::<Vec<_>>()),
+            track_ids(&queue.next_up_items.iter().collect::<Vec<_>>()),
+            queue.shuffle_order.clone(),
+            queue.current_item,
+            queue.history_index,
+            queue.history.clone(),
+        )
+    }
+
+    #[test]
+    fn undo_clear() {
+        let mut queue: OldQueue<SingleItem, CollectionItem> = OldQueue::from(
+            (0..5).map(|id| QueueItem::Single(SingleItem::Track(Track {id}))).collect::<Vec<_>>(),
+        );
+        queue.queue_next(QueueItem::Single(SingleItem::Track(Track {id: 10})));
+        queue.shuffle();
+        queue.next().unwrap();
+        queue.next().unwrap();
+        queue.previous().unwrap();
+        let before = undo_snapshot(&queue);
+
+        queue.clear();
+        let cleared = undo_snapshot(&queue);
+        queue.undo().unwrap();
+        assert_eq!(undo_snapshot(&queue), before);
+        queue.redo().unwrap();
+        assert_eq!(undo_snapshot(&queue), cleared);
+        assert!(queue.is_empty());
+        assert!(matches!(queue.redo(), Err(QueueError::NothingToRedo)));
+    }
+
+    #[test]
+    fn undo_shuffle() {
+        let mut queue: OldQueue<SingleItem, CollectionItem> = OldQueue::from(
+            (0..6).map(|id| QueueItem::Single(SingleItem::Track(Track {id}))).collect::<Vec<_>>(),
+        );
+        queue.next().unwrap();
+        let before = undo_snapshot(&queue);
+
+        queue.shuffle();
+        let shuffled = undo_snapshot(&queue);
+        queue.undo().unwrap();
+        assert_eq!(undo_snapshot(&queue), before);
+        assert!(!queue.is_shuffled());
+        queue.redo().unwrap();
+        assert_eq!(undo_snapshot(&queue), shuffled);
+
+        queue.unshuffle();
+        queue.undo().unwrap();
+        assert_eq!(undo_snapshot(&queue), shuffled);
+    }
+
+    #[test]
+    fn undo_skips_no_ops() {
+        let mut queue: OldQueue<SingleItem, CollectionItem> = OldQueue::from(
+            (0..4).map(|id| QueueItem::Single(SingleItem::Track(Track {id}))).collect::<Vec<_>>(),
+        );
+        queue.next().unwrap();
+        queue.remove(3).unwrap();
+        assert!(queue.can_undo());
+        queue.undo().unwrap();
+
+        queue.unshuffle();
+        queue.remove_range(2..2).unwrap();
+        queue.move_item(2, 2).unwrap();
+        queue.swap(0, 0).unwrap();
+        // The redo of the removal is kept, because nothing changed since
+        assert!(!queue.can_undo());
+        assert!(queue.can_redo());
+
+        let mut queue: OldQueue<SingleItem, CollectionItem> = OldQueue::default();
+        queue.clear();
+        assert!(!queue.can_undo());
+    }
+
+    #[test]
+    fn undo_queue() {
+        let mut queue: OldQueue<SingleItem, CollectionItem> = OldQueue::from(
+            (0..2).map(|id| QueueItem::Single(SingleItem::Track(Track {id}))).collect::<Vec<_>>(),
+        );
+        queue.shuffle();
+        let before = undo_snapshot(&queue);
+
+        queue.queue(QueueItem::Single(SingleItem::Track(Track {id: 10})));
+        queue.queue_next(QueueItem::Single(SingleItem::Track(Track {id: 11})));
+        queue.next().unwrap();
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 11})))));
+
+        queue.undo().unwrap();
+        assert_eq!(queue.next_up_items.len(), 0);
+        assert_eq!(queue.items.len(), 3);
+        queue.undo().unwrap();
+        assert_eq!(undo_snapshot(&queue), before);
+        queue.undo().unwrap();
+        assert!(!queue.is_shuffled());
+        assert!(matches!(queue.undo(), Err(QueueError::NothingToUndo)));
+    }
+
+    #[test]
+    fn undo_remove() {
+        let mut queue: OldQueue<SingleItem, CollectionItem> = OldQueue::from(
+            (0..4).map(|id| QueueItem::Single(SingleItem::Track(Track {id}))).collect::<Vec<_>>(),
+        );
+        queue.shuffle_order = Some(vec![2, 0, 3, 1]);
+        queue.shuffled = true;
+        queue.next().unwrap();
+        queue.next().unwrap(); // 3
+        let before = undo_snapshot(&queue);
+
+        queue.remove_range(1..3).unwrap();
+        queue.undo().unwrap();
+        assert_eq!(undo_snapshot(&queue), before);
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 3})))));
+        queue.redo().unwrap();
+        assert_eq!(track_ids(&queue.items.iter().collect::<Vec<_>>()), vec![1, 2]);
+        assert_eq!(queue.history, vec![(ItemIndex::Queue(1), 0)]);
+    }
+
+    #[test]
+    fn undo_move() {
+        let mut queue: OldQueue<SingleItem, CollectionItem> = OldQueue::from(
+            (0..5).map(|id| QueueItem::Single(SingleItem::Track(Track {id}))).collect::<Vec<_>>(),
+        );
+        queue.next().unwrap(); // 1
+        let before = undo_snapshot(&queue);
+
+        queue.move_item(4, 0).unwrap();
+        queue.swap(1, 3).unwrap();
+        let moved = undo_snapshot(&queue);
+        queue.undo().unwrap();
+        queue.undo().unwrap();
+        assert_eq!(undo_snapshot(&queue), before);
+        queue.redo().unwrap();
+        queue.redo().unwrap();
+        assert_eq!(undo_snapshot(&queue), moved);
+    }
+
+    #[test]
+    fn undo_jump_to() {
+        let mut queue: OldQueue<SingleItem, CollectionItem> = OldQueue::from(
+            (0..5).map(|id| QueueItem::Single(SingleItem::Track(Track {id}))).collect::<Vec<_>>(),
+        );
+        let before = undo_snapshot(&queue);
+
+        queue.jump_to(3, JumpPolicy::KeepUnplayed).unwrap();
+        queue.undo().unwrap();
+        assert_eq!(undo_snapshot(&queue), before);
+    }
+
+    #[test]
+    fn undo_new_edit_clears_redo() {
+        let mut queue: OldQueue<SingleItem, CollectionItem> = OldQueue::from(
+            (0..3).map(|id| QueueItem::Single(SingleItem::Track(Track {id}))).collect::<Vec<_>>(),
+        );
+        queue.shuffle();
+        queue.undo().unwrap();
+        assert!(queue.can_redo());
+        queue.queue(QueueItem::Single(SingleItem::Track(Track {id: 10})));
+        assert!(!queue.can_redo());
+        assert!(matches!(queue.redo(), Err(QueueError::NothingToRedo)));
+    }
+
+    #[test]
+    fn undo_depth() {
+        let queue: OldQueue<SingleItem, CollectionItem> = OldQueue::from(
+            (0..3).map(|id| QueueItem::Single(SingleItem::Track(Track {id}))).collect::<Vec<_>>(),
+        );
+        let mut queue = queue.with_undo_depth(2);
+        assert_eq!(queue.undo_depth(), 2);
+        for id in 10..13 {
+            queue.queue(QueueItem::Single(SingleItem::Track(Track {id})));
+        }
+        queue.undo().unwrap();
+        queue.undo().unwrap();
+        assert!(!queue.can_undo());
+        assert_eq!(queue.len(), 4);
+
+        queue.set_undo_depth(0);
+        assert!(!queue.can_redo());
+        queue.clear();
+        assert!(matches!(queue.undo(), Err(QueueError::NothingToUndo)));
+    }
+
+    impl crate::shuffle::Grouped for SingleItem {
+        type Key = u32;
+
+        /// Tracks are grouped by their id modulo 3, like three artists.
+        fn group_key(&self) -> Option<Self::Key> {
+            match self {
+                SingleItem::Track(track) => Some(track.id % 3),
+                SingleItem::Episode(_) => None,
+            }
+        }
+    }
+
+    impl crate::shuffle::Grouped for CollectionItem {
+        type Key = u32;
+
+        fn group_key(&self) -> Option<Self::Key> {
+            None
+        }
+    }
+
+    /// Whether no two tracks with the same group are next to each other.
+    fn is_spread(ids: &[u32]) -> bool {
+        ids.windows(2).all(|pair| pair[0] % 3 != pair[1] % 3)
+    }
+
+    #[test]
+    fn shuffle_with_spread() {
+        let mut queue: OldQueue<SingleItem, CollectionItem> = OldQueue::from(
+            (0..14).map(|id| QueueItem::Single(SingleItem::Track(Track {id}))).collect::<Vec<_>>(),
+        ).with_seed(5);
+        queue.next().unwrap();
+
+        queue.shuffle_with(&crate::shuffle::Spread);
+        assert!(queue.is_shuffled());
+        let ids = track_ids(&queue.get_items().iter().collect::<Vec<_>>());
+        assert_eq!(ids[..2], [0, 1]);
+        assert!(is_spread(&ids[2..]), "{:?}", ids);
+        let mut sorted = ids.clone();
+        sorted.sort();
+        assert_eq!(sorted, (0..14).collect::<Vec<u32>>());
+
+        queue.undo().unwrap();
+        assert!(!queue.is_shuffled());
+    }
+
+    #[test]
+    fn shuffle_with_keeps_next_up_order() {
+        let mut queue: OldQueue<SingleItem, CollectionItem> = OldQueue::from(
+            (0..6).map(|id| QueueItem::Single(SingleItem::Track(Track {id}))).collect::<Vec<_>>(),
+        ).with_seed(5);
+        for id in [20, 23, 26, 21] {
+            queue.queue_next(QueueItem::Single(SingleItem::Track(Track {id})));
+        }
+
+        queue.shuffle_with(&crate::shuffle::Spread);
+        assert_eq!(track_ids(&queue.get_items().up_next), vec![20, 23, 26, 21]);
+    }
+
+    #[test]
+    fn queue_after_shuffle_with_spread() {
+        let mut queue: OldQueue<SingleItem, CollectionItem> = OldQueue::from(
+            (0..14).map(|id| QueueItem::Single(SingleItem::Track(Track {id}))).collect::<Vec<_>>(),
+        ).with_seed(5);
+        queue.next().unwrap();
+        queue.shuffle_with(&crate::shuffle::Spread);
+        let spread = track_ids(&queue.get_items().iter().collect::<Vec<_>>());
+
+        // The new item goes in an unplayed place, the spread order is kept
+        queue.queue(QueueItem::Single(SingleItem::Track(Track {id: 14})));
+        let mut ids = track_ids(&queue.get_items().iter().collect::<Vec<_>>());
+        let index = ids.iter().position(|id| *id == 14).unwrap();
+        assert!(index >= 2, "{:?}", ids);
+        ids.remove(index);
+        assert_eq!(ids, spread);
+    }
+
+    impl crate::shuffle::Weighted for SingleItem {
+        /// Track 9 is a favourite.
+        fn weight(&self) -> f64 {
+            match self {
+                SingleItem::Track(Track {id: 9}) => 1000.0,
+                _ => 1.0,
+            }
+        }
+    }
+
+    #[test]
+    fn shuffle_with_weighted() {
+        use crate::shuffle::ShuffleStrategy;
+
+        let items = || (0..10).map(|id| QueueItem::Single(SingleItem::Track(Track {id}))).collect::<Vec<_>>();
+        let mut queue: OldQueue<SingleItem, SimpleCollection<SingleItem>> = OldQueue::from(items()).with_seed(2);
+        queue.shuffle_with(&ShuffleStrategy::Weighted);
+        queue.next().unwrap();
+        assert!(matches!(queue.current_track(), Ok(SingleItem::Track(Track {id: 9}))));
+
+        // The uniform strategy shuffles like the default shuffle
+        let mut queue: OldQueue<SingleItem, SimpleCollection<SingleItem>> = OldQueue::from(items()).with_seed(2);
+        let mut other: OldQueue<SingleItem, SimpleCollection<SingleItem>> = OldQueue::from(items()).with_seed(2);
+        queue.shuffle_with(&ShuffleStrategy::Uniform);
+        other.shuffle();
+        assert_eq!(queue.shuffle_order, other.shuffle_order);
+    }
+
+    #[test]
+    fn upcoming_in_history() {
+        let mut queue: OldQueue<SingleItem, SimpleCollection<SingleItem>> = OldQueue::from(vec![
+            QueueItem::Single(SingleItem::Track(Track {id: 0})),
+            album(10..13),
+            QueueItem::Single(SingleItem::Track(Track {id: 1})),
+        ]);
+        queue.next().unwrap();
+        queue.next().unwrap();
+        assert_eq!(queue.upcoming().len(), 2);
+
+        // Back to track 10, the album is only in there once
+        queue.previous().unwrap();
+        assert!(matches!(queue.current_track(), Ok(SingleItem::Track(Track {id: 10}))));
+        assert_eq!(queue.upcoming().len(), 2);
+        queue.previous().unwrap();
+        let upcoming = queue.upcoming();
+        assert_eq!(upcoming.len(), 3);
+        assert!(matches!(upcoming[0], QueueItem::Single(SingleItem::Track(Track {id: 0}))));
+        assert!(matches!(upcoming[1], QueueItem::Collection(_)));
+    }
+
+    #[test]
+    fn shuffle_in_modes() {
+        let mut queue: OldQueue<SingleItem, SimpleCollection<SingleItem>> = OldQueue::from(vec![
+            album(0..8),
+            album(10..18),
+            album(20..28),
+            QueueItem::Single(SingleItem::Track(Track {id: 30})),
+            album(40..48),
+        ]).with_seed(3);
+        queue.next().unwrap();
+        let collections_shuffled = |queue: &OldQueue<SingleItem, SimpleCollection<SingleItem>>| {
+            queue
+                .upcoming()
+                .into_iter()
+                .filter_map(|item| match item {
+                    QueueItem::Collection(collection) => Some(collection.is_shuffled()),
+                    QueueItem::Single(_) => None,
+                })
+                .collect::<Vec<bool>>()
+        };
+
+        queue.shuffle_in(ShuffleMode::Collections);
+        assert!(!queue.is_shuffled());
+        // The playing collection keeps its order
+        assert_eq!(collections_shuffled(&queue), vec![false, true, true, true]);
+        assert!(matches!(queue.current_track(), Ok(SingleItem::Track(Track {id: 1}))));
+
+        queue.shuffle_in(ShuffleMode::Items);
+        assert!(queue.is_shuffled());
+        assert_eq!(collections_shuffled(&queue), vec![false, false, false, false]);
+        assert!(matches!(queue.current_track(), Ok(SingleItem::Track(Track {id: 1}))));
+        queue.next().unwrap();
+        assert!(matches!(queue.current_track(), Ok(SingleItem::Track(Track {id: 2}))));
+    }
+
+    #[test]
+    fn shuffle_in_collections_seeded() {
+        let shuffled_tracks = || {
+            let mut queue: OldQueue<SingleItem, SimpleCollection<SingleItem>> =
+                OldQueue::from(vec![album(0..8), album(10..18)]).with_seed(4);
+            queue.shuffle_in(ShuffleMode::Collections);
+            queue.upcoming().into_iter().flat_map(|item| match item {
+                QueueItem::Collection(collection) => collection.iter().map(|track| match track {
+                    SingleItem::Track(Track {id}) => *id,
+                    SingleItem::Episode(_) => panic!("expected a track"),
+                }).collect::<Vec<u32>>(),
+                QueueItem::Single(_) => panic!("expected a collection"),
+            }).collect::<Vec<u32>>()
+        };
+
+        // The collections are shuffled with the generator of the queue
+        let ids = shuffled_tracks();
+        assert_ne!(ids[8..], (10..18).collect::<Vec<u32>>());
+        assert_eq!(shuffled_tracks(), ids);
+    }
+
+    /// Check that `peek_next` returns the tracks that `next` plays.
+    fn assert_peek_matches_next(queue: &mut OldQueue<SingleItem, SimpleCollection<SingleItem>>, n: usize) -> usize {
+        let peeked: Vec<(*const QueueItem<SingleItem, SimpleCollection<SingleItem>>, usize)> = queue
+            .peek_next(n)
+            .iter()
+            .map(|track| (track.item as *const _, track.track_index))
+            .collect();
+        for (item, track_index) in peeked.iter() {
+            queue.next().unwrap();
+            let (index, current_track_index) = queue.current_position().unwrap();
+            assert_eq!(queue.get_item(index) as *const _, *item);
+            assert_eq!(current_track_index, *track_index);
+        }
+        assert!(peeked.len() == n || queue.next().is_err());
+        peeked.len()
+    }
+
+    #[test]
+    fn peek_next_collections() {
+        let mut queue: OldQueue<SingleItem, SimpleCollection<SingleItem>> = OldQueue::from(vec![
+            QueueItem::Single(SingleItem::Track(Track {id: 0})),
+            album(10..13),
+            QueueItem::Single(SingleItem::Episode(Episode {id: 1})),
+        ]);
+        let ids: Vec<u32> = queue
+            .peek_next(3)
+            .iter()
+            .map(|track| match track.track() {
+                Ok(SingleItem::Track(Track {id})) | Ok(SingleItem::Episode(Episode {id})) => *id,
+                Err(_) => panic!("expected a track"),
+            })
+            .collect();
+        assert_eq!(ids, vec![10, 11, 12]);
+        assert_eq!(queue.peek_next(10).len(), 4);
+        assert!(queue.peek_next(0).is_empty());
+        assert_eq!(assert_peek_matches_next(&mut queue, 10), 4);
+        assert!(queue.peek_next(1).is_empty());
+        assert!(OldQueue::<SingleItem, SimpleCollection<SingleItem>>::default().peek_next(1).is_empty());
+    }
+
+    #[test]
+    fn peek_next_next_up_and_history() {
+        let mut queue: OldQueue<SingleItem, SimpleCollection<SingleItem>> = OldQueue::from(vec![
+            QueueItem::Single(SingleItem::Track(Track {id: 0})),
+            album(10..12),
+            QueueItem::Single(SingleItem::Track(Track {id: 1})),
+            QueueItem::Single(SingleItem::Track(Track {id: 2})),
+        ]);
+        queue.next().unwrap();
+        queue.queue_next(album(20..22));
+        queue.queue_next(QueueItem::Single(SingleItem::Track(Track {id: 30})));
+        queue.shuffle_order = Some(vec![0, 1, 3, 2]);
+        queue.shuffled = true;
+        queue.previous().unwrap();
+        assert_eq!(assert_peek_matches_next(&mut queue, 4), 4);
+        assert_eq!(assert_peek_matches_next(&mut queue, 10), 3);
+    }
+
+    #[test]
+    fn peek_next_repeat_after_unshuffle_strategy() {
+        for strategy in [UnshuffleStrategy::FromBeginning, UnshuffleStrategy::KeepIndex] {
+            let mut queue: OldQueue<SingleItem, SimpleCollection<SingleItem>> = OldQueue::from(
+                (0..4).map(|id| QueueItem::Single(SingleItem::Track(Track {id}))).collect::<Vec<_>>(),
+            ).with_repeat_status(Some(RepeatMode::All)).with_seed(2);
+            queue.shuffle_order = Some(vec![2, 0, 3, 1]);
+            queue.shuffled = true;
+            queue.next().unwrap(); // 0
+            queue.set_unshuffle_strategy(strategy);
+            queue.unshuffle();
+            queue.shuffle();
+
+            // Only the first new pass is reshuffled, also by peek_next
+            assert_eq!(assert_peek_matches_next(&mut queue, 20), 20);
+        }
+    }
+
+    #[test]
+    fn peek_next_repeat() {
+        let items = || vec![
+            QueueItem::Single(SingleItem::Track(Track {id: 0})),
+            album(10..12),
+            QueueItem::Single(SingleItem::Track(Track {id: 1})),
+        ];
+        for repeat_status in [Some(RepeatMode::Item), Some(RepeatMode::Container), Some(RepeatMode::All)] {
+            let mut queue: OldQueue<SingleItem, SimpleCollection<SingleItem>> =
+                OldQueue::from(items()).with_repeat_status(repeat_status);
+            queue.next().unwrap();
+            assert_eq!(assert_peek_matches_next(&mut queue, 9), 9);
+        }
+
+        let mut queue: OldQueue<SingleItem, SimpleCollection<SingleItem>> = OldQueue::from(items())
+            .with_repeat_status(Some(RepeatMode::All))
+            .with_seed(3);
+        queue.set_reshuffle_on_repeat(true);
+        queue.shuffle();
+        assert_eq!(assert_peek_matches_next(&mut queue, 20), 20);
+    }
+
+    #[test]
+    fn events_navigation() {
+        let mut queue: OldQueue<SingleItem, CollectionItem> = OldQueue::from(
+            (0..2).map(|id| QueueItem::Single(SingleItem::Track(Track {id}))).collect::<Vec<_>>(),
+        );
+        let events = queue.subscribe();
+
+        queue.next().unwrap();
+        queue.previous().unwrap();
+        queue.next().unwrap();
+        assert!(queue.next().is_err());
+        queue.set_repeat_status(Some(RepeatMode::All));
+        queue.set_repeat_status(Some(RepeatMode::All));
+        assert_eq!(
+            events.try_iter().collect::<Vec<_>>(),
+            vec![
+                QueueEvent::CurrentChanged,
+                QueueEvent::CurrentChanged,
+                QueueEvent::CurrentChanged,
+                QueueEvent::ReachedEnd,
+                QueueEvent::RepeatModeChanged(Some(RepeatMode::All)),
+            ]
+        );
+    }
+
+    #[test]
+    fn events_edits() {
+        let mut queue: OldQueue<SingleItem, CollectionItem> = OldQueue::from(
+            (0..5).map(|id| QueueItem::Single(SingleItem::Track(Track {id}))).collect::<Vec<_>>(),
+        );
+        let events = queue.subscribe();
+
+        queue.queue(QueueItem::Single(SingleItem::Track(Track {id: 5})));
+        queue.queue_next(QueueItem::Single(SingleItem::Track(Track {id: 10})));
+        queue.insert_at(2, QueueItem::Single(SingleItem::Track(Track {id: 6}))).unwrap();
+        queue.move_item(1, 4).unwrap();
+        queue.swap(3, 1).unwrap();
+        queue.remove_range(1..3).unwrap();
+        queue.remove(0).unwrap(); // 10
+        queue.jump_to(2, JumpPolicy::KeepUnplayed).unwrap();
+        queue.undo().unwrap();
+        queue.clear();
+        assert_eq!(
+            events.try_iter().collect::<Vec<_>>(),
+            vec![
+                QueueEvent::ItemsInserted { part: QueuePart::Queue, range: 5..6 },
+                QueueEvent::ItemsInserted { part: QueuePart::NextUp, range: 0..1 },
+                QueueEvent::ItemsInserted { part: QueuePart::Queue, range: 2..3 },
+                QueueEvent::ItemsMoved { from: 1, to: 4 },
+                QueueEvent::ItemsMoved { from: 3, to: 1 },
+                QueueEvent::ItemsMoved { from: 2, to: 3 },
+                QueueEvent::ItemsRemoved { part: QueuePart::Queue, range: 1..3 },
+                QueueEvent::ItemsRemoved { part: QueuePart::Queue, range: 0..1 },
+                QueueEvent::CurrentChanged,
+                QueueEvent::ItemsMoved { from: 2, to: 0 },
+                QueueEvent::CurrentChanged,
+                QueueEvent::Restored,
+                QueueEvent::Cleared,
+            ]
+        );
+
+        // Going through the history, only removing the browsed item changes it
+        let mut queue: OldQueue<SingleItem, CollectionItem> = OldQueue::from(
+            (0..5).map(|id| QueueItem::Single(SingleItem::Track(Track {id}))).collect::<Vec<_>>(),
+        );
+        queue.next().unwrap();
+        queue.next().unwrap();
+        queue.previous().unwrap(); // 1
+        let events = queue.subscribe();
+        queue.remove(4).unwrap();
+        queue.remove(0).unwrap();
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 1})))));
+        queue.remove(0).unwrap();
+        assert!(matches!(queue.get_current_item(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 2})))));
+        assert_eq!(
+            events.try_iter().collect::<Vec<_>>(),
+            vec![
+                QueueEvent::ItemsRemoved { part: QueuePart::Queue, range: 4..5 },
+                QueueEvent::ItemsRemoved { part: QueuePart::Queue, range: 0..1 },
+                QueueEvent::ItemsRemoved { part: QueuePart::Queue, range: 0..1 },
+                QueueEvent::CurrentChanged,
+            ]
+        );
+    }
+
+    #[test]
+    fn events_shuffle() {
+        let mut queue: OldQueue<SingleItem, CollectionItem> = OldQueue::from(
+            (0..4).map(|id| QueueItem::Single(SingleItem::Track(Track {id}))).collect::<Vec<_>>(),
+        );
+        let events = queue.subscribe();
+
+        queue.toggle_shuffle();
+        queue.queue(QueueItem::Single(SingleItem::Track(Track {id: 4})));
+        let index = queue.shuffle_order.as_ref().unwrap().iter().position(|raw_index| *raw_index == 4).unwrap();
+        queue.toggle_shuffle();
+        assert_eq!(
+            events.try_iter().collect::<Vec<_>>(),
+            vec![
+                QueueEvent::Shuffled,
+                QueueEvent::ItemsInserted { part: QueuePart::Queue, range: index..index + 1 },
+                QueueEvent::Unshuffled,
+            ]
+        );
+
+        // Only changes are reported
+        queue.unshuffle();
+        queue.set_repeat_status(None);
+        assert_eq!(events.try_iter().count(), 0);
+
+        // Unshuffling is reported, even if the order stays the same
+        queue.shuffle_order = Some(vec![1, 0, 2, 3, 4]);
+        queue.shuffled = true;
+        queue.unshuffle();
+        assert_eq!(queue.shuffle_order, Some(vec![1, 0, 2, 3, 4]));
+        assert_eq!(events.try_iter().collect::<Vec<_>>(), vec![QueueEvent::Unshuffled]);
+    }
+
+    #[test]
+    fn events_listeners() {
+        use std::sync::Mutex;
+
+        let mut queue: OldQueue<u32, SimpleCollection<u32>> =
+            OldQueue::from((0..3).map(QueueItem::Single).collect::<Vec<_>>());
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&received);
+        let id = queue.add_listener(move |event| sink.lock().unwrap().push(event.clone()));
+        let events = queue.subscribe();
+
+        queue.next().unwrap();
+        let mut clone = queue.clone();
+        clone.next().unwrap();
+        assert!(queue.remove_listener(id));
+        assert!(!queue.remove_listener(id));
+        queue.next().unwrap();
+        drop(events);
+        queue.next().unwrap_err();
+
+        assert_eq!(*received.lock().unwrap(), vec![QueueEvent::CurrentChanged]);
+        assert_eq!(format!("{:?}", queue.listeners), "Listeners { callbacks: 0, senders: 0 }");
+    }
+
+    #[test]
+    fn errors_empty_queue() {
+        let mut queue: OldQueue<SingleItem, CollectionItem> = OldQueue::default();
+
+        assert_eq!(queue.play(), Err(QueueError::EmptyQueue));
+        assert!(matches!(queue.next(), Err(QueueError::NotPlaying)));
+        assert!(matches!(queue.previous(), Err(QueueError::NotPlaying)));
+        assert!(matches!(queue.current_track(), Err(QueueError::NotPlaying)));
+        queue.shuffle();
+        queue.unshuffle();
+        assert!(queue.get_items().is_empty());
+        assert!(matches!(queue.get_items().get(0), Err(QueueError::IndexOutOfBounds)));
+
+        queue.queue(QueueItem::Single(SingleItem::Track(Track {id: 0})));
+        assert_eq!(queue.play(), Ok(()));
+        assert!(matches!(queue.current_track(), Ok(SingleItem::Track(Track {id: 0}))));
+        assert!(matches!(queue.next(), Err(QueueError::ReachedEnd)));
+
+        // Playing the last next up item of an otherwise empty queue
+        queue.clear();
+        queue.queue_next(QueueItem::Single(SingleItem::Track(Track {id: 1})));
+        assert_eq!(queue.play(), Ok(()));
+        assert_eq!(queue.play(), Ok(()));
+        assert!(matches!(queue.current_track(), Ok(SingleItem::Track(Track {id: 1}))));
+    }
+
+    #[test]
+    fn errors_display() {
+        let error: Box<dyn std::error::Error> = Box::new(QueueError::EmptyCollection);
+        assert_eq!(error.to_string(), "the collection is empty");
+        assert_eq!(QueueError::ReachedEnd.to_string(), "reached the end of the queue");
+        assert_eq!(QueueError::IndexOutOfBounds.to_string(), "index out of bounds");
+    }
+
+    #[test]
+    fn new_queue_single_items_simple() {
+        let mut queue: Queue<SingleItem, CollectionItem> = Queue::from(vec![
+            QueueItem::Single(SingleItem::Track(Track {id: 1})),
+            QueueItem::Single(SingleItem::Track(Track {id: 2})),
+            QueueItem::Single(SingleItem::Episode(Episode {id: 3})),
+            QueueItem::Single(SingleItem::Track(Track {id: 4})),
+        ]);
+
+        assert!(matches!(queue.current(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 1})))));
+        queue.next().unwrap();
+        assert!(matches!(queue.current(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 2})))));
+        queue.next().unwrap();
+        assert!(matches!(queue.current(), Ok(QueueItem::Single(SingleItem::Episode(Episode {id: 3})))));
+        queue.next().unwrap();
+        assert!(matches!(queue.current(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 4})))));
+        assert!(matches!(queue.next(), Err(QueueError::ReachedEnd)));
+        assert!(matches!(queue.current(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 4})))));
+        queue.previous().unwrap();
+        assert!(matches!(queue.current(), Ok(QueueItem::Single(SingleItem::Episode(Episode {id: 3})))));
+        queue.previous().unwrap();
+        queue.previous().unwrap();
+        assert!(matches!(queue.current(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 1})))));
+        assert!(matches!(queue.previous(), Err(QueueError::ReachedBeginning)));
+
+        queue.clear();
+
+        assert!(matches!(queue.current(), Err(QueueError::NotPlaying)));
+    }
+
+    #[test]
+    fn new_queue_empty() {
+        let mut queue: Queue<SingleItem, CollectionItem> = Queue::default();
+
+        assert!(queue.is_empty());
+        assert!(!queue.is_playing());
+        assert!(matches!(queue.current(), Err(QueueError::NotPlaying)));
+        assert!(matches!(queue.next(), Err(QueueError::NotPlaying)));
+        assert!(matches!(queue.previous(), Err(QueueError::NotPlaying)));
+
+        queue.queue(QueueItem::Single(SingleItem::Track(Track {id: 0})));
+
+        assert!(queue.is_playing());
+        assert!(matches!(queue.current(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 0})))));
+    }
+
+    #[test]
+    fn new_queue_shuffled() {
+        let mut queue: Queue<SingleItem, CollectionItem> = Queue::from(vec![
+            QueueItem::Single(SingleItem::Track(Track {id: 0})),
+            QueueItem::Single(SingleItem::Track(Track {id: 1})),
+            QueueItem::Single(SingleItem::Track(Track {id: 2})),
+            QueueItem::Single(SingleItem::Track(Track {id: 3})),
+        ]);
+        queue.order = Some(vec![2, 3, 0, 1]);
+        queue.shuffled = true;
+
+        assert!(matches!(queue.current(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 2})))));
+        queue.next().unwrap();
+        assert!(matches!(queue.current(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 3})))));
+        queue.next().unwrap();
+        assert!(matches!(queue.current(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 0})))));
+        queue.next().unwrap();
+        assert!(matches!(queue.current(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 1})))));
+        assert!(queue.next().is_err());
+        queue.previous().unwrap();
+        assert!(matches!(queue.current(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 0})))));
+    }
+
+    #[test]
+    fn new_queue_shuffle_keeps_played_items() {
+        let mut queue: Queue<SingleItem, CollectionItem> = Queue::from(
+            (0..16).map(|id| QueueItem::Single(SingleItem::Track(Track {id}))).collect::<Vec<_>>(),
+        );
+
+        queue.next().unwrap();
+        queue.next().unwrap(); // 2
+        queue.shuffle();
+
+        let order = queue.order.clone().unwrap();
+        assert_eq!(order[..3], [0, 1, 2]);
+        let mut sorted = order.clone();
+        sorted.sort();
+        assert_eq!(sorted, (0..16).collect::<Vec<usize>>());
+        assert!(matches!(queue.current(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 2})))));
+    }
+
+    #[test]
+    fn new_queue_unshuffle() {
+        let mut queue: Queue<SingleItem, CollectionItem> = Queue::from(
+            (0..8).map(|id| QueueItem::Single(SingleItem::Track(Track {id}))).collect::<Vec<_>>(),
+        );
+        queue.order = Some(vec![5, 2, 7, 1, 0, 3, 4, 6]);
+        queue.shuffled = true;
+
+        queue.next().unwrap(); // 2
+        queue.unshuffle();
+
+        assert_eq!(queue.order, Some(vec![5, 2, 0, 1, 3, 4, 6, 7]));
+        assert!(!queue.is_shuffled());
+        assert!(matches!(queue.current(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 2})))));
+        queue.next().unwrap();
+        assert!(matches!(queue.current(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 0})))));
+        queue.next().unwrap();
+        assert!(matches!(queue.current(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 1})))));
+    }
+
+    #[test]
+    fn new_queue_unshuffle_drops_order() {
+        let mut queue: Queue<SingleItem, CollectionItem> = Queue::from(
+            (0..4).map(|id| QueueItem::Single(SingleItem::Track(Track {id}))).collect::<Vec<_>>(),
+        );
+        queue.order = Some(vec![0, 3, 1, 2]);
+        queue.shuffled = true;
+
+        queue.unshuffle();
+
+        assert_eq!(queue.order, None);
+    }
+
+    #[test]
+    fn new_queue_unshuffle_while_playing() {
+        let mut queue: Queue<SingleItem, CollectionItem> = Queue::from(
+            (0..8).map(|id| QueueItem::Single(SingleItem::Track(Track {id}))).collect::<Vec<_>>(),
+        );
+        queue.order = Some(vec![5, 2, 7, 1, 0, 3, 4, 6]);
+        queue.shuffled = true;
+        queue.next().unwrap(); // 2
+        queue.unshuffle();
+
+        // The played items keep their order, but new items go at the end
+        queue.queue(QueueItem::Single(SingleItem::Track(Track {id: 8})));
+        assert_eq!(queue.order, Some(vec![5, 2, 0, 1, 3, 4, 6, 7, 8]));
+
+        crate::play_queue::PlayQueue::toggle_shuffle(&mut queue);
+        assert!(queue.is_shuffled());
+        let order = queue.order.clone().unwrap();
+        assert_eq!(order[..2], [5, 2]);
+        let mut sorted = order.clone();
+        sorted.sort();
+        assert_eq!(sorted, (0..9).collect::<Vec<usize>>());
+    }
+
+    #[test]
+    fn new_queue_unshuffle_twice() {
+        let mut queue: Queue<SingleItem, CollectionItem> = Queue::from(
+            (0..6).map(|id| QueueItem::Single(SingleItem::Track(Track {id}))).collect::<Vec<_>>(),
+        );
+        queue.order = Some(vec![4, 2, 5, 1, 0, 3]);
+        queue.shuffled = true;
+        queue.next().unwrap();
+        queue.next().unwrap(); // 5
+        queue.unshuffle();
+        assert_eq!(queue.order, Some(vec![4, 2, 5, 0, 1, 3]));
+
+        // Going back doesn't make the played items unshuffled again
+        queue.previous().unwrap();
+        queue.unshuffle();
+        assert_eq!(queue.order, Some(vec![4, 2, 5, 0, 1, 3]));
+    }
+
+    #[test]
+    fn new_queue_repeat_after_unshuffle() {
+        let mut queue: Queue<SingleItem, CollectionItem> = Queue::from(
+            (0..4).map(|id| QueueItem::Single(SingleItem::Track(Track {id}))).collect::<Vec<_>>(),
+        );
+        queue.order = Some(vec![3, 1, 0, 2]);
+        queue.shuffled = true;
+        queue.set_repeat_status(Some(RepeatMode::All));
+        queue.next().unwrap(); // 1
+        queue.unshuffle();
+
+        // Starting over plays the original order
+        let peeked = track_ids(&queue.peek_next(5));
+        assert_eq!(peeked, vec![0, 2, 0, 1, 2]);
+        for id in peeked {
+            queue.next().unwrap();
+            assert!(matches!(queue.current(), Ok(QueueItem::Single(SingleItem::Track(Track {id: current}))) if *current == id));
+        }
+    }
+
+    #[test]
+    fn new_queue_queue_while_shuffled() {
+        let mut queue: Queue<SingleItem, CollectionItem> = Queue::from(
+            (0..4).map(|id| QueueItem::Single(SingleItem::Track(Track {id}))).collect::<Vec<_>>(),
+        );
+        queue.order = Some(vec![2, 0, 3, 1]);
+        queue.shuffled = true;
+        queue.next().unwrap();
+        queue.next().unwrap(); // 3
+
+        queue.queue(QueueItem::Single(SingleItem::Track(Track {id: 4})));
+
+        let order = queue.order.clone().unwrap();
+        assert_eq!(order[..3], [2, 0, 3]);
+        assert!(order[3..] == [4, 1] || order[3..] == [1, 4]);
+        assert_eq!(queue.len(), 5);
+    }
+
+    #[test]
+    fn new_queue_queue_next_plays_first() {
+        let mut queue: Queue<SingleItem, CollectionItem> = Queue::from(vec![
+            QueueItem::Single(SingleItem::Track(Track {id: 0})),
+            QueueItem::Single(SingleItem::Episode(Episode {id: 0})),
+            QueueItem::Single(SingleItem::Track(Track {id: 9})),
+            QueueItem::Single(SingleItem::Track(Track {id: 7})),
+        ]);
+
+        queue.next().unwrap();
+        queue.queue_next(QueueItem::Single(SingleItem::Track(Track {id: 3})));
+        queue.queue_next(QueueItem::Single(SingleItem::Track(Track {id: 4})));
+        assert_eq!(queue.short_term_index, Some(0));
+        assert!(matches!(queue.current(), Ok(QueueItem::Single(SingleItem::Episode(Episode {id: 0})))));
+        queue.next().unwrap();
+        assert!(matches!(queue.current(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 3})))));
+        queue.next().unwrap();
+        assert!(matches!(queue.current(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 4})))));
+        assert_eq!(queue.short_term_index, Some(2));
+        queue.next().unwrap();
+        assert!(matches!(queue.current(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 9})))));
+        queue.next().unwrap();
+        assert!(matches!(queue.current(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 7})))));
+    }
+
+    #[test]
+    fn new_queue_queue_next_after_done() {
+        let mut queue: Queue<SingleItem, CollectionItem> = Queue::from(vec![
+            QueueItem::Single(SingleItem::Track(Track {id: 0})),
+            QueueItem::Single(SingleItem::Track(Track {id: 1})),
+            QueueItem::Single(SingleItem::Track(Track {id: 2})),
+        ]);
+
+        queue.queue_next(QueueItem::Single(SingleItem::Track(Track {id: 10})));
+        queue.next().unwrap();
+        queue.next().unwrap();
+        assert!(matches!(queue.current(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 1})))));
+        assert_eq!(queue.short_term_index, Some(1));
+
+        queue.queue_next(QueueItem::Single(SingleItem::Track(Track {id: 11})));
+        queue.next().unwrap();
+        assert!(matches!(queue.current(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 11})))));
+        queue.next().unwrap();
+        assert!(matches!(queue.current(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 2})))));
+    }
+
+    #[test]
+    fn new_queue_queue_next_at_end() {
+        let mut queue: Queue<SingleItem, CollectionItem> = Queue::from(vec![
+            QueueItem::Single(SingleItem::Track(Track {id: 0})),
+        ]);
+
+        queue.queue_next(QueueItem::Single(SingleItem::Track(Track {id: 10})));
+        queue.next().unwrap();
+        assert!(matches!(queue.current(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 10})))));
+        assert!(matches!(queue.next(), Err(QueueError::ReachedEnd)));
+        assert!(matches!(queue.current(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 10})))));
+
+        queue.queue(QueueItem::Single(SingleItem::Track(Track {id: 1})));
+        queue.next().unwrap();
+        assert!(matches!(queue.current(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 1})))));
+    }
+
+    #[test]
+    fn new_queue_queue_next_empty_queue() {
+        let mut queue: Queue<SingleItem, CollectionItem> = Queue::default();
+
+        queue.queue_next(QueueItem::Single(SingleItem::Track(Track {id: 10})));
+        assert!(matches!(queue.current(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 10})))));
+        queue.queue(QueueItem::Single(SingleItem::Track(Track {id: 0})));
+        assert!(matches!(queue.current(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 10})))));
+        assert!(matches!(queue.previous(), Err(QueueError::ReachedBeginning)));
+        queue.next().unwrap();
+        assert!(matches!(queue.current(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 0})))));
+    }
+
+    #[test]
+    fn new_queue_previous_through_short_term() {
+        let mut queue: Queue<SingleItem, CollectionItem> = Queue::from(vec![
+            QueueItem::Single(SingleItem::Track(Track {id: 0})),
+            QueueItem::Single(SingleItem::Track(Track {id: 1})),
+        ]);
+
+        queue.queue_next(QueueItem::Single(SingleItem::Track(Track {id: 10})));
+        queue.queue_next(QueueItem::Single(SingleItem::Track(Track {id: 11})));
+        queue.next().unwrap();
+        queue.next().unwrap();
+        assert!(matches!(queue.current(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 11})))));
+        queue.previous().unwrap();
+        assert!(matches!(queue.current(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 10})))));
+        queue.previous().unwrap();
+        assert!(matches!(queue.current(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 0})))));
+        assert!(matches!(queue.previous(), Err(QueueError::ReachedBeginning)));
+
+        queue.next().unwrap();
+        queue.next().unwrap();
+        queue.next().unwrap();
+        assert!(matches!(queue.current(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 1})))));
+        queue.previous().unwrap();
+        assert!(matches!(queue.current(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 0})))));
+    }
+
+    #[test]
+    fn new_queue_previous_from_short_term_to_queue() {
+        let mut queue: Queue<SingleItem, CollectionItem> = Queue::from(vec![
+            QueueItem::Single(SingleItem::Track(Track {id: 0})),
+            QueueItem::Single(SingleItem::Track(Track {id: 1})),
+            QueueItem::Single(SingleItem::Track(Track {id: 2})),
+        ]);
+
+        queue.next().unwrap();
+        queue.queue_next(QueueItem::Single(SingleItem::Track(Track {id: 10})));
+        queue.next().unwrap();
+        assert!(matches!(queue.current(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 10})))));
+        queue.previous().unwrap();
+        assert!(matches!(queue.current(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 1})))));
+        queue.next().unwrap();
+        assert!(matches!(queue.current(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 10})))));
+        queue.next().unwrap();
+        assert!(matches!(queue.current(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 2})))));
+    }
+
+    #[test]
+    fn new_queue_shuffle_short_term() {
+        let mut queue: Queue<SingleItem, CollectionItem> = Queue::from(vec![
+            QueueItem::Single(SingleItem::Track(Track {id: 0})),
+        ]);
+
+        for id in 10..20 {
+            queue.queue_next(QueueItem::Single(SingleItem::Track(Track {id})));
+        }
+        queue.next().unwrap();
+        queue.next().unwrap();
+        queue.shuffle();
+
+        let order = queue.short_term_order.clone().unwrap();
+        assert_eq!(order[..2], [0, 1]);
+        let mut sorted = order.clone();
+        sorted.sort();
+        assert_eq!(sorted, (0..10).collect::<Vec<usize>>());
+        assert!(matches!(queue.current(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 11})))));
+
+        queue.unshuffle();
+        assert_eq!(queue.short_term_order, None);
+        queue.next().unwrap();
+        assert!(matches!(queue.current(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 12})))));
+    }
+
+    #[test]
+    fn new_queue_history() {
+        let mut queue: Queue<SingleItem, CollectionItem> = Queue::from(vec![
+            QueueItem::Single(SingleItem::Track(Track {id: 0})),
+            QueueItem::Single(SingleItem::Track(Track {id: 1})),
+            QueueItem::Single(SingleItem::Track(Track {id: 2})),
+        ]);
+
+        queue.next().unwrap();
+        queue.queue_next(QueueItem::Single(SingleItem::Track(Track {id: 10})));
+        queue.next().unwrap();
+        queue.next().unwrap();
+        queue.previous().unwrap();
+        queue.clear();
+
+        let history = queue.history();
+        assert_eq!(history.len(), 3);
+        assert!(matches!(history[0], QueueItem::Single(SingleItem::Track(Track {id: 0}))));
+        assert!(matches!(history[1], QueueItem::Single(SingleItem::Track(Track {id: 1}))));
+        assert!(matches!(history[2], QueueItem::Single(SingleItem::Track(Track {id: 10}))));
+    }
+
+    #[test]
+    fn new_queue_len() {
+        let mut queue: Queue<SingleItem, CollectionItem> = Queue::from(vec![
+            QueueItem::Single(SingleItem::Track(Track {id: 0})),
+            QueueItem::Single(SingleItem::Track(Track {id: 1})),
+        ]);
+
+        assert_eq!(queue.len(), 2);
+        queue.queue(QueueItem::Single(SingleItem::Track(Track {id: 2})));
+        queue.queue_next(QueueItem::Single(SingleItem::Track(Track {id: 10})));
+        assert_eq!(queue.len(), 4);
+        queue.clear();
+        assert_eq!(queue.len(), 0);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn new_queue_repeat_item() {
+        let mut queue: Queue<SingleItem, CollectionItem> = Queue::from(vec![
+            QueueItem::Single(SingleItem::Track(Track {id: 0})),
+            QueueItem::Single(SingleItem::Track(Track {id: 1})),
+        ]);
+        queue.set_repeat_status(Some(RepeatMode::Item));
+
+        queue.next().unwrap();
+        queue.next().unwrap();
+        assert!(matches!(queue.current(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 0})))));
+        assert_eq!(queue.history().len(), 2);
+    }
+
+    #[test]
+    fn new_queue_repeat_all() {
+        let mut queue: Queue<SingleItem, CollectionItem> = Queue::from(vec![
+            QueueItem::Single(SingleItem::Track(Track {id: 0})),
+            QueueItem::Single(SingleItem::Track(Track {id: 1})),
+        ]);
+        queue.set_repeat_status(Some(RepeatMode::All));
+
+        queue.next().unwrap();
+        queue.next().unwrap();
+        assert!(matches!(queue.current(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 0})))));
+        queue.next().unwrap();
+        assert!(matches!(queue.current(), Ok(QueueItem::Single(SingleItem::Track(Track {id: 1})))));
+    }
+
+    #[test]
+    fn new_queue_seeded_shuffle() {
+        let items = || (0..32).map(|id| QueueItem::Single(SingleItem::Track(Track {id}))).collect::<Vec<_>>();
+        let mut queue: Queue<SingleItem, CollectionItem> = Queue::from(items()).with_seed(7);
+        let mut other: Queue<SingleItem, CollectionItem> = Queue::from(items()).with_seed(7);
+
+        assert_eq!(queue.seed(), 7);
+        queue.shuffle();
+        other.shuffle();
+        assert_eq!(queue.order, other.order);
+
+        queue.queue(QueueItem::Single(SingleItem::Track(Track {id: 32})));
+        other.queue(QueueItem::Single(SingleItem::Track(Track {id: 32})));
+        assert_eq!(queue.order, other.order);
+    }
+
+    #[test]
+    fn new_queue_shuffle_with_spread() {
+        let mut queue: Queue<SingleItem, CollectionItem> = Queue::from(
+            (0..13).map(|id| QueueItem::Single(SingleItem::Track(Track {id}))).collect::<Vec<_>>(),
+        ).with_seed(9);
+        for id in 20..29 {
+            queue.queue_next(QueueItem::Single(SingleItem::Track(Track {id})));
+        }
+
+        queue.shuffle_with(&crate::shuffle::Spread);
+        let upcoming = track_ids(&queue.upcoming());
+        assert_eq!(upcoming[0], 0);
+        assert!(is_spread(&upcoming[1..10]), "{:?}", upcoming);
+        assert!(is_spread(&upcoming[10..]), "{:?}", upcoming);
+        let mut sorted = upcoming.clone();
+        sorted.sort();
+        assert_eq!(sorted, (0..13).chain(20..29).collect::<Vec<u32>>());
+    }
+
+    #[test]
+    fn new_queue_shuffle_in_modes() {
+        let album = |ids: std::ops::Range<u32>| QueueItem::Collection(SimpleCollection::from(ids.collect::<Vec<u32>>()));
+        let mut queue: Queue<u32, SimpleCollection<u32>> = Queue::from(vec![
+            album(0..8),
+            album(10..18),
+            album(20..28),
+            album(30..38),
+        ]).with_seed(3);
+        queue.next().unwrap();
+        queue.queue_next(album(40..48));
+        let collections_shuffled = |items: Vec<&QueueItem<u32, SimpleCollection<u32>>>| {
+            items
+                .into_iter()
+                .map(|item| match item {
+                    QueueItem::Collection(collection) => collection.is_shuffled(),
+                    QueueItem::Single(_) => panic!("expected a collection"),
+                })
+                .collect::<Vec<bool>>()
+        };
+
+        queue.shuffle_in(ShuffleMode::Collections);
+        assert!(queue.order.is_none());
+        assert_eq!(collections_shuffled(queue.upcoming()), vec![false, true, true, true]);
+        // The played collection in the history isn't changed
+        assert_eq!(collections_shuffled(queue.history()), vec![false]);
+
+        queue.shuffle_in(ShuffleMode::Items);
+        assert!(queue.order.is_some());
+        assert_eq!(collections_shuffled(queue.upcoming()), vec![false, false, false, false]);
+        let mut firsts: Vec<u32> = queue
+            .upcoming()
+            .into_iter()
+            .map(|item| match item {
+                QueueItem::Collection(collection) => *collection.get_at_index(0).unwrap(),
+                QueueItem::Single(_) => panic!("expected a collection"),
+            })
+            .collect();
+        firsts.sort();
+        assert_eq!(firsts, vec![10, 20, 30, 40]);
+    }
+
+    #[test]
+    fn new_queue_shuffle_in_collections_seeded() {
+        let shuffled_tracks = || {
+            let album = |ids: std::ops::Range<u32>| QueueItem::Collection(SimpleCollection::from(ids.collect::<Vec<u32>>()));
+            let mut queue: Queue<u32, SimpleCollection<u32>> = Queue::from(vec![album(0..8), album(10..18)]).with_seed(4);
+            queue.shuffle_in(ShuffleMode::Collections);
+            queue.upcoming().into_iter().flat_map(|item| match item {
+                QueueItem::Collection(collection) => collection.iter().copied().collect::<Vec<u32>>(),
+                QueueItem::Single(_) => panic!("expected a collection"),
+            }).collect::<Vec<u32>>()
+        };
+
+        // The collections are shuffled with the generator of the queue
+        let ids = shuffled_tracks();
+        assert_eq!(ids[..8], (0..8).collect::<Vec<u32>>());
+        assert_ne!(ids[8..], (10..18).collect::<Vec<u32>>());
+        assert_eq!(shuffled_tracks(), ids);
+    }
+
+    #[test]
+    fn new_queue_peek_next() {
+        let mut queue: Queue<SingleItem, CollectionItem> = Queue::from(
+            (0..4).map(|id| QueueItem::Single(SingleItem::Track(Track {id}))).collect::<Vec<_>>(),
+        );
+        queue.order = Some(vec![0, 2, 3, 1]);
+        queue.shuffled = true;
+        queue.queue_next(QueueItem::Single(SingleItem::Track(Track {id: 10})));
+        queue.queue_next(QueueItem::Single(SingleItem::Track(Track {id: 11})));
+
+        assert_eq!(track_ids(&queue.peek_next(3)), vec![10, 11, 2]);
+        assert_eq!(track_ids(&queue.peek_next(10)), vec![10, 11, 2, 3, 1]);
+        queue.next().unwrap();
+        assert_eq!(track_ids(&queue.peek_next(10)), vec![11, 2, 3, 1]);
+        queue.next().unwrap();
+        queue.next().unwrap();
+        assert_eq!(track_ids(&queue.peek_next(10)), vec![3, 1]);
+
+        queue.set_repeat_status(Some(RepeatMode::All));
+        assert_eq!(track_ids(&queue.peek_next(6)), vec![3, 1, 0, 2, 3, 1]);
+        let peeked = track_ids(&queue.peek_next(6));
+        for id in peeked {
+            queue.next().unwrap();
+            assert_eq!(track_ids(&[queue.current().unwrap()]), vec![id]);
+        }
+
+        queue.set_repeat_status(Some(RepeatMode::Item));
+        assert_eq!(track_ids(&queue.peek_next(2)), vec![1, 1]);
+        queue.clear();
+        assert!(queue.peek_next(2).is_empty());
+    }
+
+    #[cfg(feature = "serde")]
+    fn track_id(item: &SingleItem) -> u32 {
+        match item {
+            SingleItem::Track(Track {id}) | SingleItem::Episode(Episode {id}) => *id,
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_old_queue_resumes() {
+        let mut queue: OldQueue<SingleItem, SimpleCollection<SingleItem>> = OldQueue::from(vec![
+            album(0..3),
+            QueueItem::Single(SingleItem::Track(Track {id: 10})),
+            album(20..22),
+            QueueItem::Single(SingleItem::Episode(Episode {id: 30})),
+            QueueItem::Single(SingleItem::Track(Track {id: 31})),
+        ])
+        .with_seed(3)
+        .with_repeat_status(Some(RepeatMode::All))
+        .with_unshuffle_strategy(UnshuffleStrategy::KeepIndex);
+        queue.shuffle();
+        queue.next().unwrap();
+        queue.next().unwrap();
+        queue.queue_next(album(40..42));
+        queue.previous().unwrap();
+
+        let json = serde_json::to_string(&queue).unwrap();
+        let mut loaded: OldQueue<SingleItem, SimpleCollection<SingleItem>> = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.shuffle_order, queue.shuffle_order);
+        assert_eq!(loaded.history, queue.history);
+        assert_eq!(loaded.history_index, queue.history_index);
+        assert_eq!(loaded.unshuffle_strategy(), UnshuffleStrategy::KeepIndex);
+        assert_eq!(loaded.undo_depth(), queue.undo_depth());
+        assert!(!loaded.can_undo());
+        for _ in 0..20 {
+            assert_eq!(loaded.current_track().map(track_id), queue.current_track().map(track_id));
+            loaded.next().unwrap();
+            queue.next().unwrap();
+        }
+        loaded.shuffle();
+        queue.shuffle();
+        assert_eq!(loaded.shuffle_order, queue.shuffle_order);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_queue_resumes() {
+        let mut queue: Queue<SingleItem, CollectionItem> = Queue::from(
+            (0..8).map(|id| QueueItem::Single(SingleItem::Track(Track {id}))).collect::<Vec<_>>(),
+        )
+        .with_seed(5);
+        queue.next().unwrap();
+        queue.queue_next(QueueItem::Single(SingleItem::Track(Track {id: 10})));
+        queue.queue_next(QueueItem::Single(SingleItem::Track(Track {id: 11})));
+        queue.queue_next(QueueItem::Single(SingleItem::Track(Track {id: 12})));
+        queue.shuffle();
+        queue.next().unwrap();
+
+        let json = serde_json::to_string(&queue).unwrap();
+        let mut loaded: Queue<SingleItem, CollectionItem> = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.order, queue.order);
+        assert_eq!(loaded.short_term_order, queue.short_term_order);
+        assert_eq!(loaded.short_term_index, queue.short_term_index);
+        assert_eq!(track_ids(&loaded.history()), track_ids(&queue.history()));
+        loop {
+            assert_eq!(
+                loaded.current().map(|item| track_ids(&[item])),
+                queue.current().map(|item| track_ids(&[item])),
+            );
+            let result = queue.next();
+            assert_eq!(loaded.next(), result);
+            if result.is_err() {
+                break;
+            }
+        }
+    }
+
+    /// Property tests that run random operations on an [OldQueue] with single
+    /// items, and compare it with a simple model of what should happen. The
+    /// model doesn't know the shuffle order, so while the queue is shuffled it
+    /// only checks that the item that plays is one that didn't play yet.
+    mod model {
+        use super::*;
+        use crate::item::tests::id;
+        use crate::play_queue::PlayQueue;
+        use proptest::prelude::*;
+
+        type Item = QueueItem<u32, SimpleCollection<u32>>;
+
+        #[derive(Clone, Debug)]
+        enum Operation {
+            /// Queue a single item, or a collection with the given amount of
+            /// tracks.
+            Queue(Option<usize>),
+            QueueNext(Option<usize>),
+            Play,
+            Next,
+            Previous,
+            Shuffle,
+            ShuffleIn(ShuffleMode),
+            Unshuffle,
+            Clear,
+            /// Remove the item at the given index in the playback order. Only
+            /// [OldQueue] can remove items.
+            Remove(usize),
+            SetRepeat(Option<RepeatMode>),
+            SetUnshuffleStrategy(UnshuffleStrategy),
+            SetReshuffleOnRepeat(bool),
+        }
+
+        fn shape() -> impl Strategy<Value = Option<usize>> {
+            prop_oneof![
+                3 => Just(None),
+                1 => (1..4usize).prop_map(Some),
+            ]
+        }
+
+        fn operation() -> impl Strategy<Value = Operation> {
+            prop_oneof![
+                3 => shape().prop_map(Operation::Queue),
+                2 => shape().prop_map(Operation::QueueNext),
+                1 => Just(Operation::Play),
+                6 => Just(Operation::Next),
+                3 => Just(Operation::Previous),
+                2 => Just(Operation::Shuffle),
+                1 => prop_oneof![
+                    Just(ShuffleMode::Items),
+                    Just(ShuffleMode::Collections),
+                ]
+                .prop_map(Operation::ShuffleIn),
+                2 => Just(Operation::Unshuffle),
+                1 => Just(Operation::Clear),
+                2 => (0..4usize).prop_map(Operation::Remove),
+                2 => prop_oneof![
+                    Just(None),
+                    Just(Some(RepeatMode::Item)),
+                    Just(Some(RepeatMode::Container)),
+                    Just(Some(RepeatMode::All)),
+                ]
+                .prop_map(Operation::SetRepeat),
+                2 => prop_oneof![
+                    Just(UnshuffleStrategy::PlayUnplayed),
+                    Just(UnshuffleStrategy::KeepIndex),
+                    Just(UnshuffleStrategy::KeepRawIndex),
+                    Just(UnshuffleStrategy::FromBeginning),
+                ]
+                .prop_map(Operation::SetUnshuffleStrategy),
+                1 => any::<bool>().prop_map(Operation::SetReshuffleOnRepeat),
+            ]
+        }
+
+        fn new_item(id: u32, shape: Option<usize>) -> Item {
+            match shape {
+                None => QueueItem::Single(id),
+                // Every track has the id of the collection
+                Some(tracks) => QueueItem::Collection(SimpleCollection::from(vec![id; tracks])),
+            }
+        }
+
+        /// Which queue the model describes, see [PlayQueue] for the
+        /// differences.
+        #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+        enum Kind {
+            Old,
+            New,
+        }
+
+        /// Everything the model checks of a queue.
+        #[derive(Debug)]
+        struct Snapshot {
+            /// The id and the track index of the current item.
+            current: Option<(u32, usize)>,
+            playing: bool,
+            shuffled: bool,
+            len: usize,
+            history: Vec<(u32, usize)>,
+            /// The current item and the items after it, unless the queue is
+            /// going through the history.
+            upcoming: Option<Vec<u32>>,
+            /// The items in playback order, including the ones that played.
+            order: Vec<u32>,
+            /// The next up items in playback order, including the ones that
+            /// played.
+            next_up: Vec<u32>,
+        }
+
+        /// A queue that is checked against the model.
+        trait Subject: PlayQueue<u32, SimpleCollection<u32>> + Clone {
+            const KIND: Kind;
+
+            fn new(items: Vec<Item>, seed: u64) -> Self;
+
+            /// Run the operations that aren't part of [PlayQueue].
+            fn run_other(&mut self, operation: &Operation) -> Result<(), QueueError>;
+
+            /// Whether the indices of the current item point to an item, so
+            /// the other methods don't panic.
+            fn current_in_bounds(&self) -> bool;
+
+            /// The id and the track index of the next `n` tracks, see
+            /// [OldQueue::peek_next] and [Queue::peek_next].
+            fn peek(&self, n: usize) -> Vec<(u32, usize)>;
+
+            fn snapshot(&self) -> Snapshot;
+        }
+
+        impl Subject for OldQueue<u32, SimpleCollection<u32>> {
+            const KIND: Kind = Kind::Old;
+
+            fn new(items: Vec<Item>, seed: u64) -> Self {
+                OldQueue::from(items).with_seed(seed)
+            }
+
+            fn run_other(&mut self, operation: &Operation) -> Result<(), QueueError> {
+                match *operation {
+                    Operation::ShuffleIn(mode) => self.shuffle_in(mode),
+                    Operation::SetUnshuffleStrategy(strategy) => self.set_unshuffle_strategy(strategy),
+                    Operation::SetReshuffleOnRepeat(reshuffle) => self.set_reshuffle_on_repeat(reshuffle),
+                    Operation::Remove(index) => return self.remove(index),
+                    _ => unreachable!("{:?} is part of PlayQueue", operation),
+                }
+                Ok(())
+            }
+
+            fn current_in_bounds(&self) -> bool {
+                let present = match (self.current_item, self.current_next_up_item) {
+                    // While a next up item plays, the index can be right
+                    // after the last item
+                    (Some(index), Some(next_up_index)) => {
+                        index <= self.playback_len() && next_up_index < self.next_up_items.len()
+                    }
+                    (Some(index), None) => index < self.playback_len(),
+                    (None, _) => true,
+                };
+                present && self.history_index.is_none_or(|history_index| history_index < self.history.len())
+            }
+
+            fn peek(&self, n: usize) -> Vec<(u32, usize)> {
+                self.peek_next(n).iter().map(|track| (id(track.item), track.track_index)).collect()
+            }
+
+            fn snapshot(&self) -> Snapshot {
+                let item_id = |index: ItemIndex| id(self.get_item(index));
+                Snapshot {
+                    current: self.current_position().ok().map(|(index, track_index)| (item_id(index), track_index)),
+                    playing: self.is_playing(),
+                    shuffled: self.is_shuffled(),
+                    len: self.len(),
+                    history: self.history.iter().map(|(index, track_index)| (item_id(*index), *track_index)).collect(),
+                    upcoming: self.history_index.is_none().then(|| self.upcoming().into_iter().map(id).collect()),
+                    order: (0..self.playback_len()).map(|i| item_id(ItemIndex::Queue(self.raw_index(i)))).collect(),
+                    next_up: self.next_up_items.iter().map(id).collect(),
+                }
+            }
+        }
+
+        impl Subject for Queue<u32, SimpleCollection<u32>> {
+            const KIND: Kind = Kind::New;
+
+            fn new(items: Vec<Item>, seed: u64) -> Self {
+                Queue::from(items).with_seed(seed)
+            }
+
+            fn run_other(&mut self, operation: &Operation) -> Result<(), QueueError> {
+                match *operation {
+                    // Only PlayUnplayed is supported, and items can't be
+                    // removed
+                    Operation::SetUnshuffleStrategy(_) | Operation::SetReshuffleOnRepeat(_) | Operation::Remove(_) => {}
+                    Operation::ShuffleIn(mode) => self.shuffle_in(mode),
+                    _ => unreachable!("{:?} is part of PlayQueue", operation),
+                }
+                Ok(())
+            }
+
+            fn current_in_bounds(&self) -> bool {
+                let short_term_index = self.short_term_index.unwrap_or(0);
+                let index = match self.index {
+                    // While a short term item plays, the index can be right
+                    // after the last item
+                    Some(index) if self.playing_short_term => index <= self.queue.len(),
+                    Some(index) => index < self.queue.len(),
+                    None => true,
+                };
+                index
+                    && short_term_index <= self.short_term_queue.len()
+                    && (!self.playing_short_term || short_term_index > 0)
+            }
+
+            fn peek(&self, n: usize) -> Vec<(u32, usize)> {
+                self.peek_next(n).into_iter().map(|item| (id(item), 0)).collect()
+            }
+
+            fn snapshot(&self) -> Snapshot {
+                Snapshot {
+                    current: self.current().ok().map(|item| (id(item), 0)),
+                    playing: self.is_playing(),
+                    shuffled: self.is_shuffled(),
+                    len: self.queue.len(),
+                    history: self.history().into_iter().map(|item| (id(item), 0)).collect(),
+                    upcoming: Some(self.upcoming().into_iter().map(id).collect()),
+                    order: (0..self.queue.len()).map(|i| id(&self.queue[Self::raw_index(&self.order, i)])).collect(),
+                    next_up: (0..self.short_term_queue.len())
+                        .map(|i| id(&self.short_term_queue[Self::raw_index(&self.short_term_order, i)]))
+                        .collect(),
+                }
+            }
+        }
+
+        /// Run the operation on the queue, where new items get the given id.
+        fn run<Q: Subject>(queue: &mut Q, operation: &Operation, id: u32) -> Result<(), QueueError> {
+            match *operation {
+                Operation::Queue(shape) => queue.queue(new_item(id, shape)),
+                Operation::QueueNext(shape) => queue.queue_next(new_item(id, shape)),
+                Operation::Play => return queue.play(),
+                Operation::Next => return queue.next(),
+                Operation::Previous => return queue.previous(),
+                Operation::Shuffle => queue.shuffle(),
+                Operation::Unshuffle => queue.unshuffle(),
+                Operation::Clear => queue.clear(),
+                Operation::SetRepeat(repeat) => queue.set_repeat_status(repeat),
+                _ => return queue.run_other(operation),
+            }
+            Ok(())
+        }
+
+        /// The queue put `expected[from..]` in a random order. Check that it
+        /// still has the same items and take over its order.
+        fn adopt(expected: &mut Vec<u32>, actual: &[u32], from: usize) -> Result<(), TestCaseError> {
+            prop_assert_eq!(actual.len(), expected.len());
+            prop_assert_eq!(&actual[..from], &expected[..from]);
+            let mut shuffled = actual[from..].to_vec();
+            let mut unplayed = expected[from..].to_vec();
+            shuffled.sort();
+            unplayed.sort();
+            prop_assert_eq!(shuffled, unplayed);
+            *expected = actual.to_vec();
+            Ok(())
+        }
+
+        /// The queue put `id` at a random place in `expected[from..]`. Check
+        /// that the other items kept their order and take over the place.
+        fn adopt_inserted(expected: &mut Vec<u32>, actual: &[u32], from: usize, id: u32) -> Result<(), TestCaseError> {
+            let position = actual
+                .iter()
+                .position(|other| *other == id)
+                .ok_or_else(|| TestCaseError::fail(format!("{} wasn't queued", id)))?;
+            prop_assert!(position >= from, "{} was put in the played part", id);
+            expected.insert(position, id);
+            prop_assert_eq!(&expected[..], actual);
+            Ok(())
+        }
+
+        /// What the queue should look like. Items get increasing ids, so
+        /// sorting ids sorts items in the order they were added. The orders
+        /// the queue decides randomly are checked and then taken over, so
+        /// the model always knows the exact playback order.
+        #[derive(Debug)]
+        struct Model {
+            kind: Kind,
+            next_id: u32,
+            /// The amount of tracks of every item, by id.
+            tracks: Vec<usize>,
+            /// The ids of the items of the queue, in the order they were
+            /// added.
+            items: Vec<u32>,
+            /// The playback order of `items`. Depending on the unshuffle
+            /// strategy, items can be in it more than once or not at all.
+            order: Vec<u32>,
+            /// Whether no unshuffle strategy changed which items are in
+            /// `order` since it last contained every item once.
+            complete: bool,
+            /// The amount of places in `order` that played in this pass.
+            boundary: usize,
+            next_up: Vec<u32>,
+            /// The amount of items in `next_up` that played.
+            next_up_index: usize,
+            /// The current item when not going through the history.
+            current: Option<(u32, usize)>,
+            playing_next_up: bool,
+            /// The tracks that played, oldest first.
+            history: Vec<(u32, usize)>,
+            /// The index in `history` if the user went back.
+            history_index: Option<usize>,
+            shuffled: bool,
+            repeat: Option<RepeatMode>,
+            strategy: UnshuffleStrategy,
+            reshuffle: bool,
+        }
+
+        impl Model {
+            fn new(kind: Kind, shapes: &[Option<usize>]) -> Self {
+                let items: Vec<u32> = (0..shapes.len() as u32).collect();
+                Self {
+                    kind,
+                    next_id: items.len() as u32,
+                    tracks: shapes.iter().map(|shape| shape.unwrap_or(1)).collect(),
+                    current: items.first().map(|id| (*id, 0)),
+                    boundary: items.len().min(1),
+                    order: items.clone(),
+                    complete: true,
+                    items,
+                    next_up: Vec::new(),
+                    next_up_index: 0,
+                    playing_next_up: false,
+                    history: Vec::new(),
+                    history_index: None,
+                    shuffled: false,
+                    repeat: None,
+                    strategy: UnshuffleStrategy::PlayUnplayed,
+                    reshuffle: false,
+                }
+            }
+
+            fn current(&self) -> Option<(u32, usize)> {
+                match self.history_index {
+                    Some(history_index) => Some(self.history[history_index]),
+                    None => self.current,
+                }
+            }
+
+            /// The amount of tracks that play for the item. [Queue] plays a
+            /// collection as one item.
+            fn track_count(&self, id: u32) -> usize {
+                match self.kind {
+                    Kind::Old => self.tracks[id as usize],
+                    Kind::New => 1,
+                }
+            }
+
+            fn new_id(&mut self, shape: Option<usize>) -> u32 {
+                self.tracks.push(shape.unwrap_or(1));
+                self.next_id += 1;
+                self.next_id - 1
+            }
+
+            fn plays_every_item_once(&self) -> bool {
+                let mut order = self.order.clone();
+                order.sort();
+                order == self.items
+            }
+
+            /// Start a new pass with [RepeatMode::All].
+            fn start_pass(&mut self, after: &Snapshot) -> Result<(), TestCaseError> {
+                if !self.shuffled {
+                    self.order = self.items.clone();
+                } else if self.kind == Kind::Old && (self.reshuffle || !self.plays_every_item_once()) {
+                    self.order = self.items.clone();
+                    adopt(&mut self.order, &after.order, 0)?;
+                }
+                prop_assert!(self.plays_every_item_once());
+                self.complete = true;
+                self.boundary = 1;
+                self.current = Some((self.order[0], 0));
+                self.playing_next_up = false;
+                Ok(())
+            }
+
+            fn shuffle(&mut self, after: &Snapshot) -> Result<(), TestCaseError> {
+                if self.kind == Kind::Old && self.current.is_none() {
+                    // Not playing, start over from all the items
+                    self.order = self.items.clone();
+                    self.complete = true;
+                }
+                let boundary = self.boundary.min(self.order.len());
+                adopt(&mut self.order, &after.order, boundary)?;
+                if self.kind == Kind::New {
+                    adopt(&mut self.next_up, &after.next_up, self.next_up_index)?;
+                }
+                self.shuffled = true;
+                Ok(())
+            }
+
+            fn unshuffle(&mut self) {
+                if !self.shuffled {
+                    return;
+                }
+                self.shuffled = false;
+                if self.kind == Kind::Old && self.current.is_none() {
+                    self.order = self.items.clone();
+                    self.complete = true;
+                    return;
+                }
+                let strategy = match self.kind {
+                    Kind::Old => self.strategy,
+                    Kind::New => UnshuffleStrategy::PlayUnplayed,
+                };
+                // The places that played stay the same, the strategy decides
+                // what plays after them. Ids are in the order the items were
+                // added.
+                let mut unplayed = self.order.split_off(self.boundary);
+                let last_played = self.order.last().copied();
+                let unplayed = match strategy {
+                    UnshuffleStrategy::PlayUnplayed => {
+                        unplayed.sort();
+                        unplayed
+                    }
+                    // The items that were added after the last one that played
+                    UnshuffleStrategy::KeepIndex => self
+                        .items
+                        .iter()
+                        .copied()
+                        .filter(|id| last_played.is_none_or(|last_played| *id > last_played))
+                        .collect(),
+                    // The items that come after as many items as played
+                    UnshuffleStrategy::KeepRawIndex => self.items.iter().copied().skip(self.order.len()).collect(),
+                    UnshuffleStrategy::FromBeginning => self.items.clone(),
+                };
+                self.order.extend(unplayed);
+                if strategy != UnshuffleStrategy::PlayUnplayed {
+                    self.complete = false;
+                }
+                if self.kind == Kind::New {
+                    self.next_up[self.next_up_index..].sort();
+                }
+            }
+
+            /// Remove the item with the given id from the queue and the
+            /// history, see [OldQueue::remove].
+            fn remove(&mut self, id: u32, after: &Snapshot) -> Result<(), TestCaseError> {
+                let removes_present = !self.playing_next_up && self.current.is_some_and(|(current, _)| current == id);
+                let played = self.order[..self.boundary].iter().filter(|other| **other == id).count();
+                self.items.retain(|other| *other != id);
+                self.order.retain(|other| *other != id);
+                self.boundary -= played;
+                let kept_before = self
+                    .history_index
+                    .map(|history_index| self.history[..history_index].iter().filter(|(other, _)| *other != id).count());
+                self.history.retain(|(other, _)| *other != id);
+                // Keep browsing the history at the track after the removed
+                // ones, if there is one
+                self.history_index = kept_before.filter(|history_index| *history_index < self.history.len());
+                if removes_present {
+                    // The same as going to the next item
+                    if self.next_up_index < self.next_up.len() {
+                        self.current = Some((self.next_up[self.next_up_index], 0));
+                        self.next_up_index += 1;
+                        self.playing_next_up = true;
+                    } else if self.boundary < self.order.len() {
+                        self.current = Some((self.order[self.boundary], 0));
+                        self.boundary += 1;
+                    } else {
+                        self.current = None;
+                        self.history_index = None;
+                        self.boundary = 0;
+                    }
+                }
+                if self.order.is_empty() && !self.items.is_empty() {
+                    // The unshuffle strategy left out the other items, they
+                    // have to be able to play
+                    self.order = self.items.clone();
+                    self.complete = true;
+                    if self.shuffled {
+                        adopt(&mut self.order, &after.order, 0)?;
+                    }
+                }
+                Ok(())
+            }
+
+            /// Go to the next track, see [OldQueue::next] and [Queue::next].
+            fn next(&mut self, after: &Snapshot) -> Result<Result<(), QueueError>, TestCaseError> {
+                let Some(current) = self.current else {
+                    return Ok(Err(QueueError::NotPlaying));
+                };
+                if let Some(history_index) = self.history_index {
+                    self.history_index = Some(history_index + 1).filter(|index| *index < self.history.len());
+                    return Ok(Ok(()));
+                }
+                let (id, track_index) = current;
+                let repeats_item = match self.kind {
+                    Kind::Old => self.repeat == Some(RepeatMode::Item),
+                    Kind::New => matches!(self.repeat, Some(RepeatMode::Item) | Some(RepeatMode::Container)),
+                };
+                if repeats_item {
+                    // Stay at the same track
+                } else if track_index + 1 < self.track_count(id) {
+                    self.current = Some((id, track_index + 1));
+                } else if self.repeat == Some(RepeatMode::Container) {
+                    self.current = Some((id, 0));
+                } else if self.next_up_index < self.next_up.len() {
+                    self.current = Some((self.next_up[self.next_up_index], 0));
+                    self.next_up_index += 1;
+                    self.playing_next_up = true;
+                } else if self.boundary < self.order.len() {
+                    self.current = Some((self.order[self.boundary], 0));
+                    self.boundary += 1;
+                    self.playing_next_up = false;
+                } else if self.repeat == Some(RepeatMode::All) && !self.order.is_empty() {
+                    self.start_pass(after)?;
+                } else {
+                    return Ok(Err(QueueError::ReachedEnd));
+                }
+                self.history.push(current);
+                Ok(Ok(()))
+            }
+
+            /// Go to the previous track. [OldQueue] goes back through the
+            /// history, [Queue] goes back in the playback order.
+            fn previous(&mut self) -> Result<(), QueueError> {
+                if self.current.is_none() {
+                    return Err(QueueError::NotPlaying);
+                }
+                match self.kind {
+                    Kind::Old => match self.history_index {
+                        Some(history_index) if history_index > 0 => {
+                            self.history_index = Some(history_index - 1);
+                        }
+                        None if !self.history.is_empty() => {
+                            self.history_index = Some(self.history.len() - 1);
+                        }
+                        _ => match self.repeat {
+                            Some(RepeatMode::Item) | Some(RepeatMode::Container) => {}
+                            Some(RepeatMode::All) if self.history_index.is_none() && !self.order.is_empty() => {
+                                // Nothing played yet, go to the last track
+                                let id = self.order[self.order.len() - 1];
+                                self.current = Some((id, self.track_count(id) - 1));
+                                self.boundary = self.order.len();
+                                self.playing_next_up = false;
+                            }
+                            _ => return Err(QueueError::ReachedBeginning),
+                        },
+                    },
+                    Kind::New => {
+                        if self.playing_next_up && self.next_up_index > 1 {
+                            self.next_up_index -= 1;
+                            self.current = Some((self.next_up[self.next_up_index - 1], 0));
+                        } else if self.playing_next_up && self.boundary > 0 {
+                            // Back to the item before the short term queue,
+                            // which plays again after it
+                            self.current = Some((self.order[self.boundary - 1], 0));
+                            self.next_up_index = 0;
+                            self.playing_next_up = false;
+                        } else if !self.playing_next_up && self.boundary > 1 {
+                            self.boundary -= 1;
+                            self.current = Some((self.order[self.boundary - 1], 0));
+                        } else {
+                            return Err(QueueError::ReachedBeginning);
+                        }
+                    }
+                }
+                Ok(())
+            }
+
+            /// Apply the operation, with `after` the queue after it. Returns
+            /// the expected result of the operation.
+            fn apply(&mut self, operation: &Operation, after: &Snapshot) -> Result<Result<(), QueueError>, TestCaseError> {
+                match *operation {
+                    Operation::Queue(shape) => {
+                        let id = self.new_id(shape);
+                        self.items.push(id);
+                        if self.shuffled {
+                            adopt_inserted(&mut self.order, &after.order, self.boundary, id)?;
+                        } else {
+                            self.order.push(id);
+                        }
+                        if self.kind == Kind::New && self.current.is_none() {
+                            // Queuing starts playback
+                            self.current = Some((self.order[0], 0));
+                            self.boundary = 1;
+                        }
+                    }
+                    Operation::QueueNext(shape) => {
+                        let id = self.new_id(shape);
+                        if self.kind == Kind::New && self.shuffled {
+                            adopt_inserted(&mut self.next_up, &after.next_up, self.next_up_index, id)?;
+                        } else {
+                            self.next_up.push(id);
+                        }
+                        if self.kind == Kind::New && self.current.is_none() {
+                            // Nothing to wait for, start playing right away
+                            self.current = Some((self.next_up[self.next_up_index], 0));
+                            self.next_up_index += 1;
+                            self.playing_next_up = true;
+                        }
+                    }
+                    Operation::Play => {
+                        if self.current.is_some() {
+                            return Ok(Ok(()));
+                        }
+                        if self.next_up_index < self.next_up.len() {
+                            self.current = Some((self.next_up[self.next_up_index], 0));
+                            self.next_up_index += 1;
+                            self.playing_next_up = true;
+                            self.boundary = 0;
+                        } else if !self.order.is_empty() {
+                            self.current = Some((self.order[0], 0));
+                            self.playing_next_up = false;
+                            self.boundary = 1;
+                        } else {
+                            return Ok(Err(QueueError::EmptyQueue));
+                        }
+                    }
+                    Operation::Next => return self.next(after),
+                    Operation::Previous => return Ok(self.previous()),
+                    Operation::Shuffle | Operation::ShuffleIn(ShuffleMode::Items) => self.shuffle(after)?,
+                    // Only the order inside the collections changes, which
+                    // can't be seen because their tracks have the same id
+                    Operation::ShuffleIn(ShuffleMode::Collections) => {}
+                    Operation::Unshuffle => self.unshuffle(),
+                    Operation::Clear => {
+                        self.items.clear();
+                        self.order.clear();
+                        self.complete = true;
+                        self.boundary = 0;
+                        self.next_up.clear();
+                        self.next_up_index = 0;
+                        self.current = None;
+                        self.playing_next_up = false;
+                        // Both stay shuffled, only Queue keeps the history
+                        if self.kind == Kind::Old {
+                            self.history.clear();
+                            self.history_index = None;
+                        }
+                    }
+                    Operation::Remove(_) if self.kind == Kind::New => {}
+                    Operation::Remove(index) => match self.order.get(index) {
+                        Some(id) => self.remove(*id, after)?,
+                        None => return Ok(Err(QueueError::IndexOutOfBounds)),
+                    },
+                    Operation::SetRepeat(repeat) => self.repeat = repeat,
+                    Operation::SetUnshuffleStrategy(strategy) => self.strategy = strategy,
+                    Operation::SetReshuffleOnRepeat(reshuffle) => self.reshuffle = reshuffle,
+                }
+                Ok(Ok(()))
+            }
+        }
+
+        /// Check that the queue matches the model.
+        fn check(queue: &Snapshot, model: &Model) -> Result<(), TestCaseError> {
+            prop_assert_eq!(queue.current, model.current());
+            prop_assert_eq!(queue.playing, model.current.is_some());
+            prop_assert_eq!(queue.shuffled, model.shuffled);
+            prop_assert_eq!(queue.len, model.items.len());
+            prop_assert_eq!(&queue.history, &model.history);
+            prop_assert_eq!(&queue.order, &model.order);
+            prop_assert_eq!(&queue.next_up, &model.next_up);
+            if model.complete {
+                // Only the unshuffle strategies can skip or repeat items
+                let mut order = queue.order.clone();
+                order.sort();
+                prop_assert_eq!(&order, &model.items);
+            }
+            if let Some(ref upcoming) = queue.upcoming {
+                let expected: Vec<u32> = model
+                    .current
+                    .map(|(id, _)| id)
+                    .into_iter()
+                    .chain(model.next_up[model.next_up_index..].iter().copied())
+                    .chain(model.order[model.boundary..].iter().copied())
+                    .collect();
+                prop_assert_eq!(upcoming, &expected);
+            }
+            Ok(())
+        }
+
+        /// Check that peeking shows the tracks that play next.
+        fn check_peek<Q: Subject>(queue: &Q) -> Result<(), TestCaseError> {
+            const PEEK: usize = 8;
+            let peeked = queue.peek(PEEK);
+            let mut queue = queue.clone();
+            let mut played = Vec::new();
+            while played.len() < PEEK && queue.next().is_ok() {
+                prop_assert!(queue.current_in_bounds());
+                played.extend(queue.snapshot().current);
+            }
+            prop_assert_eq!(peeked, played);
+            Ok(())
+        }
+
+        fn matches_model<Q: Subject>(
+            shapes: &[Option<usize>],
+            seed: u64,
+            operations: &[Operation],
+        ) -> Result<(), TestCaseError> {
+            let items = shapes.iter().enumerate().map(|(id, shape)| new_item(id as u32, *shape)).collect();
+            let mut queue = Q::new(items, seed);
+            let mut model = Model::new(Q::KIND, shapes);
+            check(&queue.snapshot(), &model)?;
+            for operation in operations {
+                let history_before = model.history.clone();
+                let result = run(&mut queue, operation, model.next_id);
+                prop_assert!(queue.current_in_bounds(), "{:?} left the current item out of bounds", operation);
+                let after = queue.snapshot();
+                let expected = model.apply(operation, &after)?;
+                prop_assert_eq!(result, expected, "{:?}", operation);
+                check(&after, &model)?;
+                check_peek(&queue)?;
+                // The history only grows, unless items are removed from
+                // OldQueue or it's cleared
+                if Q::KIND == Kind::New || !matches!(operation, Operation::Clear | Operation::Remove(_)) {
+                    prop_assert!(after.history.starts_with(&history_before));
+                }
+            }
+            Ok(())
+        }
+
+        proptest! {
+            // The unshuffle strategies and removals only go wrong after a
+            // few rare operations in a row
+            #![proptest_config(ProptestConfig::with_cases(1024))]
+
+            #[test]
+            fn old_queue_matches_model(
+                shapes in prop::collection::vec(shape(), 0..6),
+                seed in any::<u64>(),
+                operations in prop::collection::vec(operation(), 0..60),
+            ) {
+                matches_model::<OldQueue<u32, SimpleCollection<u32>>>(&shapes, seed, &operations)?;
+            }
+
+            #[test]
+            fn queue_matches_model(
+                shapes in prop::collection::vec(shape(), 0..6),
+                seed in any::<u64>(),
+                operations in prop::collection::vec(operation(), 0..60),
+            ) {
+                matches_model::<Queue<u32, SimpleCollection<u32>>>(&shapes, seed, &operations)?;
+            }
+        }
     }
 }
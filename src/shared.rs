@@ -0,0 +1,195 @@
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use crate::item::{QueueItem, QueueableCollection};
+use crate::queue::{Queue, QueueError};
+
+/// A handle to a [Queue] that can be used from several threads at once, for
+/// example by an audio thread and a UI thread. Cloning the handle results in
+/// another handle to the same queue.
+///
+/// Every method locks the queue for the whole operation, so compound
+/// operations like [SharedQueue::next] can't be interleaved with changes
+/// from other threads. Use [SharedQueue::with] for other compound
+/// operations.
+#[derive(Debug)]
+pub struct SharedQueue<I, C: QueueableCollection> {
+    queue: Arc<Mutex<Queue<I, C>>>,
+}
+
+/// The items that are playing after an operation on a [SharedQueue]. The
+/// items are shared with the queue, so they can be used after it's unlocked.
+#[derive(Debug)]
+pub struct NowPlaying<I, C: QueueableCollection> {
+    /// The currently playing item.
+    pub current: Arc<QueueItem<I, C>>,
+    /// The item that plays after the current one, or None if the queue
+    /// reaches the end after it.
+    pub next: Option<Arc<QueueItem<I, C>>>,
+}
+
+impl<I, C: QueueableCollection> Clone for SharedQueue<I, C> {
+    fn clone(&self) -> Self {
+        Self {
+            queue: Arc::clone(&self.queue),
+        }
+    }
+}
+
+impl<I, C: QueueableCollection> Clone for NowPlaying<I, C> {
+    fn clone(&self) -> Self {
+        Self {
+            current: Arc::clone(&self.current),
+            next: self.next.as_ref().map(Arc::clone),
+        }
+    }
+}
+
+impl<I, C: QueueableCollection> From<Queue<I, C>> for SharedQueue<I, C> {
+    fn from(queue: Queue<I, C>) -> Self {
+        Self {
+            queue: Arc::new(Mutex::new(queue)),
+        }
+    }
+}
+
+impl<I, C: QueueableCollection> Default for SharedQueue<I, C> {
+    fn default() -> Self {
+        Self::from(Queue::default())
+    }
+}
+
+impl<I, C: QueueableCollection> SharedQueue<I, C> {
+    /// Lock the queue until the guard is dropped. If another thread panicked
+    /// while it had the queue locked, the queue is used as that thread left
+    /// it.
+    pub fn lock(&self) -> MutexGuard<'_, Queue<I, C>> {
+        self.queue.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Run `f` with the queue locked, and return its result.
+    pub fn with<R, F: FnOnce(&mut Queue<I, C>) -> R>(&self, f: F) -> R {
+        f(&mut self.lock())
+    }
+
+    /// Go to the next item, see [Queue::next], and return the new current
+    /// and next items.
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&self) -> Result<NowPlaying<I, C>, QueueError> {
+        let mut queue = self.lock();
+        queue.next()?;
+        Self::now_playing_locked(&queue)
+    }
+
+    /// Go to the previous item, see [Queue::previous], and return the new
+    /// current and next items.
+    pub fn previous(&self) -> Result<NowPlaying<I, C>, QueueError> {
+        let mut queue = self.lock();
+        queue.previous()?;
+        Self::now_playing_locked(&queue)
+    }
+
+    /// Get the current and next items.
+    pub fn now_playing(&self) -> Result<NowPlaying<I, C>, QueueError> {
+        Self::now_playing_locked(&self.lock())
+    }
+
+    /// Add an item to the end of the queue, see [Queue::queue].
+    pub fn queue(&self, item: QueueItem<I, C>) {
+        self.lock().queue(item);
+    }
+
+    /// Add an item to the short term queue, see [Queue::queue_next].
+    pub fn queue_next(&self, item: QueueItem<I, C>) {
+        self.lock().queue_next(item);
+    }
+
+    fn now_playing_locked(queue: &Queue<I, C>) -> Result<NowPlaying<I, C>, QueueError> {
+        Ok(NowPlaying {
+            current: queue.current_arc().ok_or(QueueError::NotPlaying)?,
+            next: queue.next_arc(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::item::tests::id;
+    use crate::item::SimpleCollection;
+    use crate::queue::RepeatMode;
+    use std::thread;
+
+    type TestQueue = SharedQueue<u32, SimpleCollection<u32>>;
+
+    #[test]
+    fn shared_queue_is_send_and_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<Queue<u32, SimpleCollection<u32>>>();
+        assert_send_sync::<TestQueue>();
+        assert_send_sync::<NowPlaying<u32, SimpleCollection<u32>>>();
+    }
+
+    #[test]
+    fn shared_queue_now_playing() {
+        let queue = TestQueue::from(Queue::from((0..3).map(QueueItem::Single).collect::<Vec<_>>()));
+
+        let playing = queue.now_playing().unwrap();
+        assert_eq!((id(&playing.current), playing.next.as_deref().map(id)), (0, Some(1)));
+        queue.queue_next(QueueItem::Single(10));
+        let playing = queue.next().unwrap();
+        assert_eq!((id(&playing.current), playing.next.as_deref().map(id)), (10, Some(1)));
+        let playing = queue.next().unwrap();
+        assert_eq!((id(&playing.current), playing.next.as_deref().map(id)), (1, Some(2)));
+        let playing = queue.next().unwrap();
+        assert_eq!((id(&playing.current), playing.next.as_deref().map(id)), (2, None));
+        assert!(matches!(queue.next(), Err(QueueError::ReachedEnd)));
+
+        queue.with(|queue| queue.set_repeat_status(Some(RepeatMode::All)));
+        let playing = queue.now_playing().unwrap();
+        assert_eq!((id(&playing.current), playing.next.as_deref().map(id)), (2, Some(0)));
+        queue.lock().set_repeat_status(Some(RepeatMode::Item));
+        let playing = queue.previous().unwrap();
+        assert_eq!((id(&playing.current), playing.next.as_deref().map(id)), (1, Some(1)));
+
+        queue.lock().clear();
+        assert!(matches!(queue.now_playing(), Err(QueueError::NotPlaying)));
+    }
+
+    #[test]
+    fn shared_queue_threads() {
+        let queue = TestQueue::default();
+        let handles: Vec<_> = (0..4)
+            .map(|thread| {
+                let queue = queue.clone();
+                thread::spawn(move || {
+                    for i in 0..25 {
+                        queue.queue(QueueItem::Single(thread * 25 + i));
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        assert_eq!(queue.lock().len(), 100);
+        // Any of the threads could have queued the first item
+        let first = id(&queue.now_playing().unwrap().current);
+
+        let players: Vec<_> = (0..4)
+            .map(|_| {
+                let queue = queue.clone();
+                thread::spawn(move || {
+                    let mut played = Vec::new();
+                    while let Ok(playing) = queue.next() {
+                        played.push(id(&playing.current));
+                    }
+                    played
+                })
+            })
+            .collect();
+        let mut played: Vec<u32> = players.into_iter().flat_map(|handle| handle.join().unwrap()).collect();
+        played.push(first);
+        played.sort();
+        assert_eq!(played, (0..100).collect::<Vec<u32>>());
+    }
+}
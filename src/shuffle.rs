@@ -0,0 +1,310 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use rand::seq::SliceRandom;
+use rand::{Rng, RngCore};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::item::{QueueItem, QueueableCollection};
+
+/// An algorithm that decides the playback order of shuffled items, see
+/// [OldQueue::shuffle_with](crate::queue::OldQueue::shuffle_with) and
+/// [Queue::shuffle_with](crate::queue::Queue::shuffle_with).
+pub trait ShuffleAlgorithm<T: ?Sized> {
+    /// Get the shuffled order of `items`, as indices in `items`. The order
+    /// has to contain every index exactly once. All randomness should come
+    /// from `rng`, so the queue can shuffle the same way for the same seed.
+    fn order(&self, items: &[&T], rng: &mut dyn RngCore) -> Vec<usize>;
+}
+
+/// Shuffle the items uniformly at random, with a Fisher-Yates shuffle. This
+/// is what [OldQueue::shuffle](crate::queue::OldQueue::shuffle) uses.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Uniform;
+
+impl<T: ?Sized> ShuffleAlgorithm<T> for Uniform {
+    fn order(&self, items: &[&T], rng: &mut dyn RngCore) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..items.len()).collect();
+        order.shuffle(rng);
+        order
+    }
+}
+
+/// An item that belongs to a group of similar items, like the tracks of an
+/// artist or album.
+pub trait Grouped {
+    type Key: Eq + Hash;
+
+    /// Get the key of the group of the item, or None if the item doesn't
+    /// belong to a group.
+    fn group_key(&self) -> Option<Self::Key>;
+}
+
+/// Single items use the key of the item, collections the key of the
+/// collection.
+impl<I, C> Grouped for QueueItem<I, C>
+where
+    I: Grouped,
+    C: QueueableCollection + Grouped<Key = I::Key>,
+{
+    type Key = I::Key;
+
+    fn group_key(&self) -> Option<Self::Key> {
+        match self {
+            QueueItem::Single(item) => item.group_key(),
+            QueueItem::Collection(collection) => collection.group_key(),
+        }
+    }
+}
+
+/// Shuffle the items so the items of the same group are spread out as evenly
+/// as possible over the playback order, instead of ending up next to each
+/// other by chance.
+///
+/// The items of every group are shuffled and spaced out evenly over the
+/// order, starting at a random offset. Groups with a lot of items get a spot
+/// every few items, small groups are placed randomly. Items without a group
+/// are placed randomly too. If all the groups have the same size, no two
+/// items of the same group end up next to each other.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Spread;
+
+impl<T: Grouped + ?Sized> ShuffleAlgorithm<T> for Spread {
+    fn order(&self, items: &[&T], rng: &mut dyn RngCore) -> Vec<usize> {
+        // Groups in order of appearance, so the result only depends on the rng
+        let mut group_indices: HashMap<T::Key, usize> = HashMap::new();
+        let mut groups: Vec<Vec<usize>> = Vec::new();
+        for (index, item) in items.iter().enumerate() {
+            match item.group_key() {
+                Some(key) => {
+                    let group = *group_indices.entry(key).or_insert_with(|| {
+                        groups.push(Vec::new());
+                        groups.len() - 1
+                    });
+                    groups[group].push(index);
+                }
+                None => groups.push(vec![index]),
+            }
+        }
+
+        let mut positions: Vec<(f64, usize)> = Vec::with_capacity(items.len());
+        for mut group in groups {
+            group.shuffle(rng);
+            let spacing = 1.0 / group.len() as f64;
+            let offset = rng.gen_range(0.0..spacing);
+            for (i, index) in group.into_iter().enumerate() {
+                positions.push((offset + i as f64 * spacing, index));
+            }
+        }
+        positions.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+        positions.into_iter().map(|(_, index)| index).collect()
+    }
+}
+
+/// An item with a weight, like a rating. Items with a higher weight tend to
+/// play earlier with [ShuffleStrategy::Weighted].
+pub trait Weighted {
+    /// Get the weight of the item. Items with a weight of 0 or less play
+    /// after all the other items.
+    fn weight(&self) -> f64;
+}
+
+/// Single items use the weight of the item, collections the average weight
+/// of their tracks. Empty collections have a weight of 0.
+impl<I, C> Weighted for QueueItem<I, C>
+where
+    I: Weighted,
+    C: QueueableCollection<Item = I>,
+{
+    fn weight(&self) -> f64 {
+        match self {
+            QueueItem::Single(item) => item.weight(),
+            QueueItem::Collection(collection) if collection.is_empty() => 0.0,
+            QueueItem::Collection(collection) => {
+                collection.iter().map(Weighted::weight).sum::<f64>() / collection.len() as f64
+            }
+        }
+    }
+}
+
+/// A shuffle algorithm for items with a weight that can be picked at
+/// runtime, for example from a setting. [Uniform] and [Spread] can be used
+/// for any items.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum ShuffleStrategy {
+    /// Shuffle the items uniformly at random, like [Uniform].
+    #[default]
+    Uniform,
+    /// Pick the items one by one at random, where the chance of an item to
+    /// be picked next is proportional to its weight. Items with a higher
+    /// weight tend to play earlier, but every item still plays once.
+    Weighted,
+}
+
+impl<T: Weighted + ?Sized> ShuffleAlgorithm<T> for ShuffleStrategy {
+    fn order(&self, items: &[&T], rng: &mut dyn RngCore) -> Vec<usize> {
+        match self {
+            ShuffleStrategy::Uniform => Uniform.order(items, rng),
+            ShuffleStrategy::Weighted => weighted_order(items, rng),
+        }
+    }
+}
+
+/// Weighted random sampling without replacement, using the keys of
+/// Efraimidis and Spirakis: sorting the items by `ln(u) / weight`, with `u`
+/// uniformly random in (0, 1], picks every next item with a chance
+/// proportional to its weight.
+fn weighted_order<T: Weighted + ?Sized>(items: &[&T], rng: &mut dyn RngCore) -> Vec<usize> {
+    let mut keyed: Vec<(f64, usize)> = Vec::with_capacity(items.len());
+    let mut weightless: Vec<usize> = Vec::new();
+    for (index, item) in items.iter().enumerate() {
+        let weight = item.weight();
+        if weight > 0.0 {
+            let u: f64 = 1.0 - rng.gen::<f64>();
+            keyed.push((u.ln() / weight, index));
+        } else {
+            // Also catches NaN
+            weightless.push(index);
+        }
+    }
+    keyed.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+    weightless.shuffle(rng);
+    keyed.into_iter().map(|(_, index)| index).chain(weightless).collect()
+}
+
+/// Shuffle `indices` with `algorithm`, where `item` gets the item an index
+/// points to.
+pub(crate) fn shuffle_raw_indices<'a, T, A>(
+    indices: &mut [usize],
+    item: impl Fn(usize) -> &'a T,
+    algorithm: &A,
+    rng: &mut dyn RngCore,
+) where
+    T: 'a + ?Sized,
+    A: ShuffleAlgorithm<T> + ?Sized,
+{
+    let items: Vec<&T> = indices.iter().map(|index| item(*index)).collect();
+    let order = algorithm.order(&items, rng);
+    debug_assert_eq!(order.len(), indices.len(), "the order has to contain every item once");
+    let shuffled: Vec<usize> = order.into_iter().map(|i| indices[i]).collect();
+    indices.copy_from_slice(&shuffled);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::seeded_rng;
+
+    struct Track {
+        artist: Option<&'static str>,
+    }
+
+    impl Grouped for Track {
+        type Key = &'static str;
+
+        fn group_key(&self) -> Option<Self::Key> {
+            self.artist
+        }
+    }
+
+    /// The smallest distance in the order between two tracks of the same
+    /// artist.
+    fn min_distance(tracks: &[Track], order: &[usize]) -> usize {
+        let mut min = usize::MAX;
+        for (i, a) in order.iter().enumerate() {
+            for (j, b) in order.iter().enumerate().skip(i + 1) {
+                if tracks[*a].artist.is_some() && tracks[*a].artist == tracks[*b].artist {
+                    min = min.min(j - i);
+                }
+            }
+        }
+        min
+    }
+
+    impl Weighted for f64 {
+        fn weight(&self) -> f64 {
+            *self
+        }
+    }
+
+    #[test]
+    fn weighted_favours_heavy_items() {
+        let weights = [1.0, 1.0, 20.0, 1.0, 1.0];
+        let items: Vec<&f64> = weights.iter().collect();
+        let first_count = (0..200)
+            .filter(|seed| ShuffleStrategy::Weighted.order(&items, &mut seeded_rng(*seed))[0] == 2)
+            .count();
+        // The heavy item is picked first with a chance of 20 / 24
+        assert!(first_count > 150, "{}", first_count);
+
+        let mut order = ShuffleStrategy::Weighted.order(&items, &mut seeded_rng(0));
+        order.sort();
+        assert_eq!(order, vec![0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn weighted_weightless_items_last() {
+        let weights = [0.0, 2.0, f64::NAN, -1.0, 0.5];
+        let items: Vec<&f64> = weights.iter().collect();
+        for seed in 0..20 {
+            let order = ShuffleStrategy::Weighted.order(&items, &mut seeded_rng(seed));
+            let mut first: Vec<usize> = order[..2].to_vec();
+            first.sort();
+            assert_eq!(first, vec![1, 4]);
+            let mut last: Vec<usize> = order[2..].to_vec();
+            last.sort();
+            assert_eq!(last, vec![0, 2, 3]);
+        }
+    }
+
+    #[test]
+    fn weighted_collections() {
+        use crate::item::SimpleCollection;
+
+        let album: QueueItem<f64, SimpleCollection<f64>> =
+            QueueItem::Collection(SimpleCollection::from(vec![1.0, 2.0, 6.0]));
+        let empty: QueueItem<f64, SimpleCollection<f64>> = QueueItem::Collection(SimpleCollection::from(vec![]));
+        assert_eq!(album.weight(), 3.0);
+        assert_eq!(empty.weight(), 0.0);
+        assert_eq!(QueueItem::<f64, SimpleCollection<f64>>::Single(4.0).weight(), 4.0);
+        assert_eq!(ShuffleStrategy::default(), ShuffleStrategy::Uniform);
+    }
+
+    #[test]
+    fn uniform_is_permutation() {
+        let items = [&0, &1, &2, &3, &4];
+        let mut order = Uniform.order(&items, &mut seeded_rng(1));
+        order.sort();
+        assert_eq!(order, vec![0, 1, 2, 3, 4]);
+        assert!(Uniform.order(&[] as &[&u32], &mut seeded_rng(1)).is_empty());
+    }
+
+    #[test]
+    fn spread_spreads_groups() {
+        let artists = ["a", "a", "a", "a", "b", "b", "b", "b", "c", "c", "c", "c"];
+        let tracks: Vec<Track> = artists.iter().map(|artist| Track {artist: Some(artist)}).collect();
+        let items: Vec<&Track> = tracks.iter().collect();
+        for seed in 0..50 {
+            let order = Spread.order(&items, &mut seeded_rng(seed));
+            let mut sorted = order.clone();
+            sorted.sort();
+            assert_eq!(sorted, (0..12).collect::<Vec<usize>>());
+            assert!(min_distance(&tracks, &order) >= 2, "seed {}: {:?}", seed, order);
+        }
+    }
+
+    #[test]
+    fn spread_is_seeded() {
+        let tracks: Vec<Track> = [Some("a"), None, Some("b"), Some("a"), None, Some("b")]
+            .into_iter()
+            .map(|artist| Track {artist})
+            .collect();
+        let items: Vec<&Track> = tracks.iter().collect();
+        assert_eq!(Spread.order(&items, &mut seeded_rng(4)), Spread.order(&items, &mut seeded_rng(4)));
+        let mut order = Spread.order(&items, &mut seeded_rng(4));
+        order.sort();
+        assert_eq!(order, (0..6).collect::<Vec<usize>>());
+    }
+}
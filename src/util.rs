@@ -1,8 +1,59 @@
-use rand::thread_rng;
 use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
 
-pub fn shuffled_vec(size: usize) -> Vec<usize> {
+/// The random number generator used for shuffling. Its output is stable
+/// across versions, so a seed always results in the same shuffle.
+pub type QueueRng = ChaCha8Rng;
+
+pub fn shuffled_vec<R: Rng + ?Sized>(size: usize, rng: &mut R) -> Vec<usize> {
     let mut vec: Vec<usize> = (0..size).collect();
-    vec.shuffle(&mut thread_rng());
+    vec.shuffle(rng);
     vec
 }
+
+pub fn seeded_rng(seed: u64) -> QueueRng {
+    QueueRng::seed_from_u64(seed)
+}
+
+/// Reorder `vec` so the item at index i comes from index `order[i]`, and
+/// return the order that reverts it. `order` has to contain every index in
+/// `vec` exactly once.
+pub fn reorder<T>(vec: &mut Vec<T>, order: &[usize]) -> Vec<usize> {
+    let mut items: Vec<Option<T>> = std::mem::take(vec).into_iter().map(Some).collect();
+    let mut inverse = vec![0; items.len()];
+    for (new_index, &old_index) in order.iter().enumerate() {
+        inverse[old_index] = new_index;
+        if let Some(item) = items[old_index].take() {
+            vec.push(item);
+        }
+    }
+    inverse
+}
+
+/// Remove the items at the given indices, which have to be in ascending
+/// order, and return them together with their indices.
+pub fn remove_indices<T>(vec: &mut Vec<T>, indices: Vec<usize>) -> Vec<(usize, T)> {
+    // Removing from the back doesn't change the smaller indices
+    let mut removed: Vec<(usize, T)> = indices.into_iter().rev().map(|i| (i, vec.remove(i))).collect();
+    removed.reverse();
+    removed
+}
+
+/// Insert the items at the given indices, which have to be in ascending
+/// order, and return the indices. This reverts [remove_indices].
+pub fn insert_indices<T>(vec: &mut Vec<T>, items: Vec<(usize, T)>) -> Vec<usize> {
+    items
+        .into_iter()
+        .map(|(i, item)| {
+            vec.insert(i, item);
+            i
+        })
+        .collect()
+}
+
+/// Whether `order` contains every index below `len` exactly once.
+pub fn is_permutation(order: &[usize], len: usize) -> bool {
+    let mut seen = vec![false; len];
+    order.len() == len && order.iter().all(|&i| i < len && !std::mem::replace(&mut seen[i], true))
+}